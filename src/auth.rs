@@ -46,14 +46,12 @@ pub fn get_credentials(nexus_url: &Url) -> anyhow::Result<(String, String)> {
         let netrc = netrc_rs::Netrc::parse(s, false).unwrap();
         for machine in &netrc.machines {
             match &machine.name {
-                None => {}
-                Some(name) => {
-                    if nexus_host.as_str() == name {
-                        let user = machine.login.clone().unwrap();
-                        let password = machine.password.clone().unwrap();
-                        return Ok((user, password));
-                    }
+                Some(name) if nexus_host.as_str() == name => {
+                    let user = machine.login.clone().unwrap();
+                    let password = machine.password.clone().unwrap();
+                    return Ok((user, password));
                 }
+                _ => {}
             }
         }
         anyhow::bail!("Hostname not found in .netrc: '{:?}'", nexus_url.host())
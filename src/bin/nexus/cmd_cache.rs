@@ -0,0 +1,51 @@
+use clap::Subcommand;
+
+use nexus_client::cache::{Cache, CacheStats};
+
+use crate::output::{CacheRecord, OutputFormat, print_item};
+use crate::table::human_size;
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show how many files the cache holds, and their size
+    Stats,
+    /// Remove everything from the cache
+    Clean,
+}
+
+pub fn cmd_cache(cache_command: CacheCommands, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let Some(cache) = Cache::from_env()? else {
+        anyhow::bail!("Cache is not enabled; set NEXUS_CACHE_DIR to use it");
+    };
+    let (stats, removed) = match cache_command {
+        CacheCommands::Stats => (cache.stats()?, false),
+        CacheCommands::Clean => {
+            let removed = cache.clean()?;
+            log::info!("Removed {} entries and {} objects ({})", removed.entries, removed.objects, human_size(removed.size));
+            (removed, true)
+        }
+    };
+    let CacheStats { entries, objects, size } = stats;
+    match output {
+        Some(output) => print_item(output, &CacheRecord {
+            cache_dir: cache.dir().display().to_string(),
+            entries,
+            objects,
+            size,
+            max_size: cache.max_size(),
+        })?,
+        None if removed => {
+            println!("Directory:       {}", cache.dir().display());
+            println!("Removed entries: {entries}");
+            println!("Removed objects: {objects}");
+            println!("Removed bytes:   {size}");
+        }
+        None => {
+            println!("Directory: {}", cache.dir().display());
+            println!("Entries:   {entries}");
+            println!("Objects:   {objects}");
+            println!("Size:      {} of {}", human_size(size), human_size(cache.max_size()));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG, HeaderMap, LAST_MODIFIED};
+use tokio::io::AsyncWriteExt;
+
+use nexus_client::{ByteRange, NexusClient, NexusRepository};
+
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{OutputFormat, print_list, StatRecord};
+use crate::table::{Cell, print_aligned};
+
+/// Streams content of remote files to stdout.
+pub async fn cmd_cat(nexus: &NexusClient, nexus_uris: &[NexusRemoteUri], range: Option<ByteRange>) -> anyhow::Result<()> {
+    let mut stdout = tokio::io::stdout();
+    for nexus_uri in nexus_uris {
+        let path = file_path(nexus_uri)?;
+        let written = nexus.read_file(&nexus_uri.repo_id, path, range, &mut stdout).await?;
+        log::debug!("{written} bytes of {nexus_uri} written");
+    }
+    Ok(())
+}
+
+/// Prints beginning of a remote file - either `bytes`, requested as a range, or `lines`, stopping the download once they are complete.
+pub async fn cmd_head(nexus: &NexusClient, nexus_uri: &NexusRemoteUri, lines: usize, bytes: Option<u64>) -> anyhow::Result<()> {
+    let path = file_path(nexus_uri)?;
+    let mut stdout = tokio::io::stdout();
+    if let Some(bytes) = bytes {
+        if bytes > 0 {
+            let range = ByteRange { start: 0, end: Some(bytes - 1) };
+            nexus.read_file(&nexus_uri.repo_id, path, Some(range), &mut stdout).await?;
+        }
+        return Ok(());
+    }
+    let http_response = nexus.get_file(&nexus_uri.repo_id, path, None).await?;
+    let mut stream = http_response.bytes_stream();
+    let mut remaining = lines;
+    while remaining > 0 {
+        let Some(chunk) = stream.next().await else {
+            break;
+        };
+        let chunk = chunk?;
+        let mut end = chunk.len();
+        for (index, _) in chunk.iter().enumerate().filter(|(_, &byte)| byte == b'\n') {
+            remaining -= 1;
+            if remaining == 0 {
+                end = index + 1;
+                break;
+            }
+        }
+        stdout.write_all(&chunk[..end]).await?;
+    }
+    stdout.flush().await?;
+    Ok(())
+}
+
+/// Shows metadata of remote files. Checksums come from the storage item info, with fallback to plain `HEAD` request.
+pub async fn cmd_stat(nexus: &NexusClient, nexus_uris: &[NexusRemoteUri], output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let mut records = Vec::with_capacity(nexus_uris.len());
+    for nexus_uri in nexus_uris {
+        let path = file_path(nexus_uri)?;
+        let request = NexusRepository::nexus_readonly(&nexus_uri.repo_id).describe(path);
+        let info = match nexus.execute(request).await {
+            Ok(response) => response.parsed().await,
+            Err(e) => Err(e),
+        };
+        let record = match info {
+            Ok(info) => StatRecord {
+                remote_uri: nexus_uri.to_string(),
+                size: u64::try_from(info.size).ok(),
+                last_modified: info.last_changed_at().map(|time| time.to_rfc3339()),
+                content_type: non_empty(info.mime_type),
+                sha1: non_empty(info.sha1_hash),
+                md5: non_empty(info.md5_hash),
+                etag: None,
+            },
+            Err(e) => {
+                log::debug!("No storage item info for {nexus_uri}, using HEAD: {e}");
+                let headers = nexus.head_file(&nexus_uri.repo_id, path).await?;
+                stat_from_headers(nexus_uri, &headers)
+            }
+        };
+        records.push(record);
+    }
+    match output {
+        Some(output) => print_list(output, &records)?,
+        None => {
+            for (index, record) in records.into_iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                print_stat(record);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stat_from_headers(nexus_uri: &NexusRemoteUri, headers: &HeaderMap) -> StatRecord {
+    let header = |name| headers.get(name)
+        .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+        .map(|value| value.to_string());
+    let last_modified = header(LAST_MODIFIED)
+        .and_then(|value| DateTime::parse_from_rfc2822(&value).ok())
+        .map(|time| time.with_timezone(&Utc).to_rfc3339());
+    StatRecord {
+        remote_uri: nexus_uri.to_string(),
+        size: header(CONTENT_LENGTH).and_then(|value| value.parse().ok()),
+        last_modified,
+        content_type: header(CONTENT_TYPE),
+        sha1: None,
+        md5: None,
+        etag: header(ETAG),
+    }
+}
+
+fn print_stat(record: StatRecord) {
+    let StatRecord { remote_uri, size, last_modified, content_type, sha1, md5, etag } = record;
+    let fields = [
+        ("file", Some(remote_uri)),
+        ("size", size.map(|size| size.to_string())),
+        ("last_modified", last_modified),
+        ("content_type", content_type),
+        ("sha1", sha1),
+        ("md5", md5),
+        ("etag", etag),
+    ];
+    let rows = fields.into_iter()
+        .filter_map(|(key, value)| value.map(|value| vec![Cell::from(key.to_string()), Cell::from(value)]))
+        .collect();
+    print_aligned(None, rows, &[]);
+}
+
+fn file_path(nexus_uri: &NexusRemoteUri) -> anyhow::Result<&str> {
+    if nexus_uri.is_dir() {
+        anyhow::bail!("File path expected: {nexus_uri}");
+    }
+    Ok(&nexus_uri.repo_path)
+}
+
+fn non_empty(text: String) -> Option<String> {
+    (!text.is_empty()).then_some(text)
+}
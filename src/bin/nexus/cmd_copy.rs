@@ -0,0 +1,132 @@
+use nexus_client::{NexusClient, NexusRepository};
+use nexus_client::remote_sync::{copy_file, copy_tree, CopiedFile, verify_copy, walk_remote_tree};
+
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{CopyRecord, OutputFormat, print_list};
+
+/// Copies remote file or directory to another place, possibly in another repository.
+/// With `remove_source` (= move), the source is removed once all copies are verified.
+pub async fn cmd_copy(nexus: &NexusClient, source: &NexusRemoteUri, target: &NexusRemoteUri, remove_source: bool, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let copied = if source.is_dir() {
+        let target_root = target.repo_path_dir_or_err()?;
+        if source.repo_id == target.repo_id && target_root.starts_with(&source.repo_path) {
+            anyhow::bail!("Cannot copy {source} into itself: {target}");
+        }
+        copy_tree(nexus, &source.repo_id, &source.repo_path, &target.repo_id, target_root).await?
+    } else {
+        let target_path = if target.is_dir() {
+            let (_, file_name) = source.repo_path.rsplit_once('/').unwrap_or(("", &source.repo_path));
+            format!("{}{file_name}", target.repo_path)
+        } else {
+            target.repo_path.clone()
+        };
+        if source.repo_id == target.repo_id && source.repo_path == target_path {
+            anyhow::bail!("Source and target are the same: {source}");
+        }
+        copy_file(nexus, &source.repo_id, &source.repo_path, &target.repo_id, &target_path).await?
+    };
+    if remove_source {
+        for file in &copied {
+            verify_copy(nexus, &source.repo_id, &target.repo_id, file).await?;
+        }
+        log::info!("All {} copies verified", copied.len());
+        // only the verified files; anything added to the source meanwhile stays there
+        let source_repo = NexusRepository::nexus_readwrite(&source.repo_id);
+        for file in &copied {
+            nexus.execute(source_repo.delete(&file.source_path)).await?.check().await?;
+            log::warn!("Removed: ::/{}{}", source.repo_id, file.source_path);
+        }
+        if source.is_dir() {
+            remove_empty_dirs(nexus, &source.repo_id, &source.repo_path).await?;
+        }
+    }
+    if let Some(output) = output {
+        let records: Vec<CopyRecord> = copied.into_iter()
+            .map(|CopiedFile { source_path, target_path, size }| CopyRecord {
+                source_uri: format!("::/{}{source_path}", source.repo_id),
+                target_uri: format!("::/{}{target_path}", target.repo_id),
+                size,
+            })
+            .collect();
+        print_list(output, &records)?;
+    }
+    Ok(())
+}
+
+/// Removes directories under `root` (and `root` itself) which have no files left after moving them away.
+/// Directories with files added meanwhile stay.
+async fn remove_empty_dirs(nexus: &NexusClient, repo_id: &str, root: &str) -> anyhow::Result<()> {
+    let tree = walk_remote_tree(nexus, repo_id, root).await?;
+    let has_files = |dir: &str| tree.iter().any(|(subpath, entry)| entry.leaf && subpath.starts_with(dir));
+    // parents come before their subdirectories, and removing a parent removes them too
+    let dirs = std::iter::once("").chain(tree.iter().filter(|(_, entry)| !entry.leaf).map(|(subpath, _)| subpath.as_str()));
+    let mut empty_dirs: Vec<&str> = Vec::new();
+    for dir in dirs {
+        if !has_files(dir) && !empty_dirs.iter().any(|empty_dir| dir.starts_with(empty_dir)) {
+            empty_dirs.push(dir);
+        }
+    }
+    let source_repo = NexusRepository::nexus_readwrite(repo_id);
+    for subpath in empty_dirs {
+        nexus.execute(source_repo.delete(&format!("{root}{subpath}"))).await?.check().await?;
+        log::info!("Removed empty directory: ::/{repo_id}{root}{subpath}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nexus_uri::NexusRemoteUri;
+    use crate::test_server::{Repositories, TestServer};
+
+    use super::cmd_copy;
+
+    const FILES: [(&str, &str); 4] = [
+        ("org/example/demo/1.0/demo-1.0.pom", "<project/>"),
+        ("org/example/demo/1.0/demo-1.0.jar", "demo 1.0"),
+        ("org/example/demo/1.1/demo-1.1.jar", "demo 1.1"),
+        ("org/example/README.txt", "readme"),
+    ];
+
+    #[tokio::test]
+    async fn test_move_tree() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &FILES);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+        let source: NexusRemoteUri = "::/candidates/org/example/demo/".parse()?;
+        let target: NexusRemoteUri = "::/releases/org/example/demo/".parse()?;
+
+        cmd_copy(&nexus, &source, &target, true, None).await?;
+
+        // emptied directories are removed too
+        assert_eq!(repositories.paths("candidates"), vec!["org/", "org/example/", "org/example/README.txt"]);
+        assert_eq!(repositories.content("releases", "org/example/demo/1.0/demo-1.0.jar").as_deref(), Some("demo 1.0"));
+        assert_eq!(repositories.paths("releases").iter().filter(|path| !path.ends_with('/')).count(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_keeps_unverified_source() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &FILES);
+        let handler = repositories.handler();
+        // the copy of one file gets corrupted on its way
+        let server = TestServer::start(move |request| {
+            let mut request = request.clone();
+            if request.method == "PUT" && request.path.ends_with("demo-1.1.jar") {
+                request.body = b"demo 1.X".to_vec();
+            }
+            handler(&request)
+        }).await?;
+        let nexus = server.client()?;
+        let source: NexusRemoteUri = "::/candidates/org/example/demo/".parse()?;
+        let target: NexusRemoteUri = "::/releases/org/example/demo/".parse()?;
+
+        let result = cmd_copy(&nexus, &source, &target, true, None).await;
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("differs from its source"), "{message}");
+        assert!(server.requests().iter().all(|request| request.method != "DELETE"));
+        assert_eq!(repositories.paths("candidates").iter().filter(|path| !path.ends_with('/')).count(), 4);
+        Ok(())
+    }
+}
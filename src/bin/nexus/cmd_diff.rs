@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use nexus_client::NexusClient;
+use nexus_client::remote_sync::{diff_tree, DiffStatus, FileDifference};
+
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{DiffRecord, OutputFormat, print_list};
+use crate::table::{Cell, Color, print_aligned};
+
+pub async fn cmd_diff(nexus: &NexusClient, local_root: &Path, nexus_uri: &NexusRemoteUri, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    if !local_root.is_dir() {
+        anyhow::bail!("Local directory expected: {}", local_root.display());
+    }
+    let remote_root = nexus_uri.repo_path_dir_or_err()?;
+    let differences = diff_tree(nexus, &nexus_uri.repo_id, remote_root, local_root).await?;
+    log::info!("{} differences between {} and {nexus_uri}", differences.len(), local_root.display());
+    let records: Vec<DiffRecord> = differences.into_iter().map(diff_record).collect();
+    if let Some(output) = output {
+        return print_list(output, &records);
+    }
+    let rows = records.into_iter()
+        .map(|DiffRecord { path, status, local_size, remote_size, detail }| {
+            let color = match status {
+                "only-local" => Color::Green,
+                "only-remote" => Color::Red,
+                _ => Color::Yellow,
+            };
+            let detail = match detail.as_deref() {
+                Some("size") => format!("size {} != {}", local_size.unwrap_or_default(), remote_size.unwrap_or_default()),
+                Some(detail) => format!("{detail} differs"),
+                None => String::new(),
+            };
+            vec![Cell { text: status.to_string(), color: Some(color) }, Cell::from(path), Cell::from(detail)]
+        })
+        .collect();
+    print_aligned(None, rows, &[]);
+    Ok(())
+}
+
+fn diff_record(difference: FileDifference) -> DiffRecord {
+    let FileDifference { path, status, local_size, remote_size, detail } = difference;
+    DiffRecord {
+        path,
+        status: match status {
+            DiffStatus::OnlyLocal => "only-local",
+            DiffStatus::OnlyRemote => "only-remote",
+            DiffStatus::Differs => "differs",
+        },
+        local_size,
+        remote_size,
+        detail,
+    }
+}
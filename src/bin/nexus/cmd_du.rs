@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashSet};
+
+use clap::ValueEnum;
+
+use nexus_client::{NexusClient, remote_sync};
+use nexus_client::model::DirEntry;
+
+use crate::cmd_list::walk_tree;
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{DuRecord, OutputFormat, print_list};
+use crate::table::{Column, human_size, print_table, TableOptions};
+
+/// How `du` groups the files
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DuGrouping {
+    /// subdirectories, up to `--depth` levels below the given directory
+    Dir,
+    /// Maven groupId
+    Group,
+    /// Maven groupId:artifactId
+    Artifact,
+    /// Maven groupId:artifactId:version
+    Version,
+}
+
+/// Label for files outside Maven layout, when grouping by coordinates
+const OTHER: &str = "(other)";
+
+pub async fn cmd_du(nexus: &NexusClient, nexus_uri: &NexusRemoteUri, grouping: DuGrouping, depth: usize, table: &TableOptions, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let root = nexus_uri.repo_path_dir_or_err()?;
+    let entries = remote_sync::fetch_dir(nexus, &nexus_uri.repo_id, root).await?;
+    let mut files = Vec::new();
+    walk_tree(nexus, &nexus_uri.repo_id, entries, |_, dir_files, subdirs| {
+        files.extend(dir_files);
+        subdirs
+    }).await?;
+    let mut records = summarize(&nexus_uri.repo_id, root, &files, grouping, depth);
+    let total_size: u64 = records.iter().map(|record| record.size).sum();
+    log::info!("Total {} in {} files under {nexus_uri}", human_size(total_size), files.len());
+    match output {
+        Some(output) => print_list(output, &records),
+        None => print_table(&mut records, &du_columns(), &["size", "files", "path"], table),
+    }
+}
+
+/// Counts files and their sizes per group, ordered by the group key.
+fn summarize(repo_id: &str, root: &str, files: &[DirEntry], grouping: DuGrouping, depth: usize) -> Vec<DuRecord> {
+    let mut totals: BTreeMap<String, DuRecord> = BTreeMap::new();
+    let keys = match grouping {
+        DuGrouping::Dir => files.iter().map(|file| dir_key(repo_id, root, file, depth)).collect(),
+        grouping => coordinate_keys(files, grouping),
+    };
+    for (file, key) in files.iter().zip(keys) {
+        let record = totals.entry(key.clone()).or_insert_with(|| DuRecord { path: key, files: 0, size: 0 });
+        record.files += 1;
+        record.size += file.size_on_disk.max(0) as u64;
+    }
+    totals.into_values().collect()
+}
+
+/// The directory `depth` levels below `root`, containing the file; or the file's own directory when it is shallower.
+fn dir_key(repo_id: &str, root: &str, file: &DirEntry, depth: usize) -> String {
+    let subpath = file.relative_path.strip_prefix(root).unwrap_or(&file.relative_path);
+    let dirs: Vec<&str> = subpath.split('/').collect();
+    // the last segment is the file name
+    let dirs = &dirs[..dirs.len() - 1];
+    let dirs = &dirs[..dirs.len().min(depth)];
+    let mut key = format!("::/{repo_id}{root}");
+    for dir in dirs {
+        key.push_str(dir);
+        key.push('/');
+    }
+    key
+}
+
+/// Maven coordinates of each file. A version directory is recognized by file names starting with `<artifactId>-<version>`;
+/// other files in its artifact directory (like `maven-metadata.xml`) are counted to the artifact.
+fn coordinate_keys(files: &[DirEntry], grouping: DuGrouping) -> Vec<String> {
+    let segments: Vec<Vec<&str>> = files.iter()
+        .map(|file| file.relative_path.trim_start_matches('/').split('/').collect())
+        .collect();
+    let in_version_dir = |segments: &[&str]| match segments {
+        [.., artifact_id, version, name] if segments.len() > 3 => name.starts_with(&format!("{artifact_id}-{version}")),
+        _ => false,
+    };
+    // paths of artifact directories
+    let artifact_dirs: HashSet<String> = segments.iter()
+        .filter(|segments| in_version_dir(segments))
+        .map(|segments| segments[..segments.len() - 2].join("/"))
+        .collect();
+    segments.iter()
+        .map(|segments| {
+            let (group, artifact, version) = if in_version_dir(segments) {
+                let n = segments.len();
+                (segments[..n - 3].join("."), segments[n - 3], Some(segments[n - 2]))
+            } else if segments.len() > 2 && artifact_dirs.contains(&segments[..segments.len() - 1].join("/")) {
+                let n = segments.len();
+                (segments[..n - 2].join("."), segments[n - 2], None)
+            } else {
+                return OTHER.to_string();
+            };
+            match (grouping, version) {
+                (DuGrouping::Group, _) => group,
+                (DuGrouping::Version, Some(version)) => format!("{group}:{artifact}:{version}"),
+                _ => format!("{group}:{artifact}"),
+            }
+        })
+        .collect()
+}
+
+fn du_columns() -> Vec<Column<DuRecord>> {
+    vec![
+        Column::new("size", |record: &DuRecord| human_size(record.size).into())
+            .compare(|a, b| a.size.cmp(&b.size)),
+        Column::new("files", |record: &DuRecord| record.files.to_string().into())
+            .compare(|a, b| a.files.cmp(&b.files)),
+        Column::new("path", |record: &DuRecord| record.path.clone().into()).truncate(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use nexus_client::model::DirEntry;
+
+    use super::{coordinate_keys, dir_key, DuGrouping, summarize};
+
+    fn file(path: &str, size: i64) -> DirEntry {
+        DirEntry {
+            relative_path: path.to_string(),
+            text: path.rsplit('/').next().unwrap().to_string(),
+            leaf: true,
+            size_on_disk: size,
+            ..DirEntry::default()
+        }
+    }
+
+    fn files() -> Vec<DirEntry> {
+        vec![
+            file("/org/example/demo/1.0/demo-1.0.jar", 1000),
+            file("/org/example/demo/1.0/demo-1.0.pom", 100),
+            file("/org/example/demo/1.1/demo-1.1.jar", 1200),
+            file("/org/example/demo/maven-metadata.xml", 10),
+            file("/org/example/util/2.0/util-2.0.jar", 500),
+            file("/org/README.txt", 5),
+        ]
+    }
+
+    #[test]
+    fn test_dir_key() {
+        let jar = file("/org/example/demo/1.0/demo-1.0.jar", 1);
+        assert_eq!(dir_key("releases", "/org/", &jar, 0), "::/releases/org/");
+        assert_eq!(dir_key("releases", "/org/", &jar, 1), "::/releases/org/example/");
+        assert_eq!(dir_key("releases", "/org/", &jar, 2), "::/releases/org/example/demo/");
+        // shallower than depth
+        assert_eq!(dir_key("releases", "/org/", &jar, 10), "::/releases/org/example/demo/1.0/");
+        assert_eq!(dir_key("releases", "/org/", &file("/org/README.txt", 1), 1), "::/releases/org/");
+    }
+
+    #[test]
+    fn test_coordinate_keys() {
+        let files = files();
+        assert_eq!(coordinate_keys(&files, DuGrouping::Version), vec![
+            "org.example:demo:1.0",
+            "org.example:demo:1.0",
+            "org.example:demo:1.1",
+            // metadata belongs to the artifact, not to a version
+            "org.example:demo",
+            "org.example:util:2.0",
+            "(other)",
+        ]);
+        assert_eq!(coordinate_keys(&files, DuGrouping::Artifact), vec![
+            "org.example:demo", "org.example:demo", "org.example:demo", "org.example:demo", "org.example:util", "(other)",
+        ]);
+        assert_eq!(coordinate_keys(&files, DuGrouping::Group), vec![
+            "org.example", "org.example", "org.example", "org.example", "org.example", "(other)",
+        ]);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = |grouping, depth| -> Vec<(String, u64, u64)> {
+            summarize("releases", "/org/", &files(), grouping, depth).into_iter()
+                .map(|record| (record.path, record.files, record.size))
+                .collect()
+        };
+        assert_eq!(summary(DuGrouping::Dir, 2), vec![
+            ("::/releases/org/".to_string(), 1, 5),
+            ("::/releases/org/example/demo/".to_string(), 4, 2310),
+            ("::/releases/org/example/util/".to_string(), 1, 500),
+        ]);
+        assert_eq!(summary(DuGrouping::Dir, 0), vec![("::/releases/org/".to_string(), 6, 2815)]);
+        assert_eq!(summary(DuGrouping::Artifact, 0), vec![
+            ("(other)".to_string(), 1, 5),
+            ("org.example:demo".to_string(), 4, 2310),
+            ("org.example:util".to_string(), 1, 500),
+        ]);
+    }
+}
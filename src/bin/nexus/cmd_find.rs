@@ -0,0 +1,252 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, ValueEnum};
+
+use nexus_client::{NexusClient, remote_sync};
+use nexus_client::model::DirEntry;
+
+use crate::cmd_list::{DirPrinter, walk_tree};
+use crate::cmd_remove::{remove_paths, RemoveOptions};
+use crate::DirFormat;
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{FileRecord, OutputFormat, print_list};
+
+/// Conditions on entries found by `find`; all given ones must match.
+#[derive(Args, Clone, Debug, Default)]
+pub struct FindFilters {
+    /// file or directory name matches this glob pattern, like `*.jar`
+    #[arg(long)]
+    pub name: Option<glob::Pattern>,
+    /// modified after this date (`2026-01-01`) or RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    pub newer: Option<DateTime<Utc>>,
+    /// modified before this date (`2026-01-01`) or RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    pub older: Option<DateTime<Utc>>,
+    /// file larger than this size, like `10M`; units are `k`, `M`, `G` (powers of 1024)
+    #[arg(long)]
+    pub larger: Option<Size>,
+    /// file smaller than this size, like `512k`
+    #[arg(long)]
+    pub smaller: Option<Size>,
+    /// `f` for files, `d` for directories
+    #[arg(long = "type")]
+    pub entry_type: Option<EntryType>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EntryType {
+    #[value(name = "f")]
+    File,
+    #[value(name = "d")]
+    Directory,
+}
+
+/// Size in bytes, given with optional binary unit suffix
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Size(pub i64);
+
+impl FromStr for Size {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, multiplier) = match s.char_indices().last() {
+            Some((index, 'k' | 'K')) => (&s[..index], 1 << 10),
+            Some((index, 'm' | 'M')) => (&s[..index], 1 << 20),
+            Some((index, 'g' | 'G')) => (&s[..index], 1 << 30),
+            _ => (s, 1),
+        };
+        let number: i64 = number.parse().map_err(|_| anyhow::anyhow!("Invalid size: '{s}'"))?;
+        let size = number.checked_mul(multiplier).ok_or_else(|| anyhow::anyhow!("Invalid size: '{s}'"))?;
+        Ok(Self(size))
+    }
+}
+
+fn parse_time(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    Ok(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc))
+}
+
+impl FindFilters {
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        if self.name.as_ref().is_some_and(|name| !name.matches(&entry.text)) {
+            return false;
+        }
+        match self.entry_type {
+            Some(EntryType::File) if !entry.leaf => return false,
+            Some(EntryType::Directory) if entry.leaf => return false,
+            _ => {}
+        }
+        if self.newer.is_some() || self.older.is_some() {
+            let Some(last_modified) = entry.last_modified_at() else {
+                return false;
+            };
+            if self.newer.is_some_and(|newer| last_modified <= newer)
+                || self.older.is_some_and(|older| last_modified >= older) {
+                return false;
+            }
+        }
+        if self.larger.is_some() || self.smaller.is_some() {
+            // directories have no size
+            if !entry.leaf {
+                return false;
+            }
+            if self.larger.is_some_and(|Size(larger)| entry.size_on_disk <= larger)
+                || self.smaller.is_some_and(|Size(smaller)| entry.size_on_disk >= smaller) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// What to do with found entries, besides printing them
+pub enum FindAction {
+    Print,
+    Delete(RemoveOptions),
+    Download { local_root: PathBuf },
+}
+
+pub async fn cmd_find(nexus: NexusClient, nexus_uri: &NexusRemoteUri, filters: &FindFilters, format: DirFormat, action: FindAction, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let root = nexus_uri.repo_path_dir_or_err()?;
+    let entries = remote_sync::fetch_dir(&nexus, &nexus_uri.repo_id, root).await?;
+    let mut found = Vec::new();
+    walk_tree(&nexus, &nexus_uri.repo_id, entries, |_, files, subdirs| {
+        found.extend(subdirs.iter().chain(&files).filter(|entry| filters.matches(entry)).cloned());
+        subdirs
+    }).await?;
+    found.sort_unstable_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    log::debug!("Found {} entries", found.len());
+    match action {
+        FindAction::Print => {
+            let mut dir_printer = DirPrinter::new(format, output)?.with_full_uris(&nexus_uri.repo_id);
+            dir_printer.print_dir(&found);
+            dir_printer.finish()?;
+        }
+        FindAction::Delete(options) => {
+            // removing a directory removes everything found inside it, too
+            let mut removed: Vec<&DirEntry> = Vec::new();
+            for entry in &found {
+                if !removed.last().is_some_and(|dir| !dir.leaf && entry.relative_path.starts_with(&dir.relative_path)) {
+                    removed.push(entry);
+                }
+            }
+            if removed.is_empty() {
+                log::info!("Nothing found to remove");
+                return Ok(());
+            }
+            let nexus_uris: Vec<NexusRemoteUri> = removed.iter()
+                .map(|entry| NexusRemoteUri { repo_id: nexus_uri.repo_id.clone(), repo_path: entry.relative_path.clone() })
+                .collect();
+            remove_paths(&nexus, &nexus_uris, options, output).await?;
+        }
+        FindAction::Download { local_root } => {
+            let mut downloaded = Vec::new();
+            for entry in found.iter().filter(|entry| entry.leaf) {
+                // keep the layout relative to the searched directory
+                let subpath = entry.relative_path.strip_prefix(root).unwrap_or(&entry.relative_path);
+                let local_path = local_root.join(subpath.trim_start_matches('/'));
+                nexus.download_file(&nexus_uri.repo_id, &local_path, &entry.relative_path, true).await?;
+                log::info!("* ::/{}{} -> {}", nexus_uri.repo_id, entry.relative_path, local_path.display());
+                downloaded.push(FileRecord {
+                    local_path: local_path.display().to_string(),
+                    remote_uri: format!("::/{}{}", nexus_uri.repo_id, entry.relative_path),
+                });
+            }
+            log::info!("Downloaded {} files to {}", downloaded.len(), local_root.display());
+            if let Some(output) = output {
+                print_list(output, &downloaded)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nexus_client::model::DirEntry;
+
+    use super::{EntryType, FindFilters, parse_time, Size};
+
+    fn entry(name: &str, leaf: bool, size: i64, last_modified: &str) -> DirEntry {
+        DirEntry {
+            relative_path: format!("/org/example/{name}"),
+            text: name.to_string(),
+            leaf,
+            last_modified: last_modified.to_string(),
+            size_on_disk: size,
+            ..DirEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_size() -> anyhow::Result<()> {
+        assert_eq!("100".parse::<Size>()?, Size(100));
+        assert_eq!("512k".parse::<Size>()?, Size(512 * 1024));
+        assert_eq!("10M".parse::<Size>()?, Size(10 * 1024 * 1024));
+        assert_eq!("2g".parse::<Size>()?, Size(2 * 1024 * 1024 * 1024));
+        assert!("".parse::<Size>().is_err());
+        assert!("M".parse::<Size>().is_err());
+        assert!("10T".parse::<Size>().is_err());
+        assert!("99999999999G".parse::<Size>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_time() -> anyhow::Result<()> {
+        assert_eq!(parse_time("2026-01-01")?.to_rfc3339(), "2026-01-01T00:00:00+00:00");
+        assert_eq!(parse_time("2026-01-01T12:00:00+02:00")?.to_rfc3339(), "2026-01-01T10:00:00+00:00");
+        assert!(parse_time("01/01/2026").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches() -> anyhow::Result<()> {
+        let jar = entry("demo-1.0.jar", true, 2048, "2026-03-01 10:00:00.0 UTC");
+        let pom = entry("demo-1.0.pom", true, 100, "2025-12-01 10:00:00.0 UTC");
+        let dir = entry("1.0/", false, 0, "2026-03-01 10:00:00.0 UTC");
+
+        assert!(FindFilters::default().matches(&jar));
+        assert!(FindFilters::default().matches(&dir));
+
+        let filters = FindFilters { name: Some(glob::Pattern::new("*.jar")?), ..FindFilters::default() };
+        assert!(filters.matches(&jar));
+        assert!(!filters.matches(&pom));
+
+        let filters = FindFilters { entry_type: Some(EntryType::Directory), ..FindFilters::default() };
+        assert!(!filters.matches(&jar));
+        assert!(filters.matches(&dir));
+
+        let filters = FindFilters { newer: Some(parse_time("2026-01-01")?), ..FindFilters::default() };
+        assert!(filters.matches(&jar));
+        assert!(!filters.matches(&pom));
+        let filters = FindFilters { older: Some(parse_time("2026-01-01")?), ..FindFilters::default() };
+        assert!(!filters.matches(&jar));
+        assert!(filters.matches(&pom));
+
+        // directories have no size, so they never match size conditions
+        let filters = FindFilters { larger: Some(Size(1024)), ..FindFilters::default() };
+        assert!(filters.matches(&jar));
+        assert!(!filters.matches(&pom));
+        assert!(!filters.matches(&dir));
+        let filters = FindFilters { smaller: Some(Size(1024)), ..FindFilters::default() };
+        assert!(!filters.matches(&jar));
+        assert!(filters.matches(&pom));
+
+        // all conditions must match
+        let filters = FindFilters { name: Some(glob::Pattern::new("*.jar")?), smaller: Some(Size(1024)), ..FindFilters::default() };
+        assert!(!filters.matches(&jar));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unparseable_time_never_matches() {
+        let entry = entry("demo-1.0.jar", true, 2048, "yesterday");
+        let filters = FindFilters { newer: Some(chrono::DateTime::UNIX_EPOCH), ..FindFilters::default() };
+        assert!(!filters.matches(&entry));
+    }
+}
@@ -5,8 +5,9 @@ use nexus_client::model::DirEntry;
 
 use crate::DirFormat;
 use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{OutputFormat, print_list};
 
-pub async fn cmd_list(nexus: NexusClient, nexus_uri: &NexusRemoteUri, dir_printer: DirPrinter, recurse: bool) -> anyhow::Result<()> {
+pub async fn cmd_list(nexus: NexusClient, nexus_uri: &NexusRemoteUri, mut dir_printer: DirPrinter, recurse: bool) -> anyhow::Result<()> {
     let remote_dir = nexus_uri.repo_path_dir_or_err()?;
     // Non-recursive
     let mut entries = remote_sync::fetch_dir(&nexus, &nexus_uri.repo_id, remote_dir).await?;
@@ -15,42 +16,50 @@ pub async fn cmd_list(nexus: NexusClient, nexus_uri: &NexusRemoteUri, dir_printe
         let (files, subdirs) = split_files_subdirs(entries);
         dir_printer.print_dir(&subdirs);
         dir_printer.print_dir(&files);
-        return Ok(());
+        return dir_printer.finish();
     }
+    walk_tree(&nexus, &nexus_uri.repo_id, entries, |container, files, subdirs| {
+        if let Some(container) = container {
+            log::debug!("CONTAINER: {container:?}");
+            dir_printer.print_entry(&container);
+        }
+        dir_printer.print_dir(&files);
+        subdirs
+    }).await?;
+    dir_printer.finish()
+}
 
+/// Walks remote tree, listing directories concurrently.
+///
+/// Starting with already listed `entries` of the root, `visit` receives each listed directory (`None` for the root),
+/// with its files and subdirectories sorted by name; it returns the subdirectories to descend into.
+/// Fails with the first directory that cannot be listed.
+pub async fn walk_tree<F>(nexus: &NexusClient, repo_id: &str, entries: Vec<DirEntry>, mut visit: F) -> anyhow::Result<()>
+    where F: FnMut(Option<DirEntry>, Vec<DirEntry>, Vec<DirEntry>) -> Vec<DirEntry>
+{
     let (sender, mut receiver) = tokio::sync::mpsc::channel::<DirChunk>(1000);
     sender.clone().send(DirChunk {
         container: None,
-        entries,
+        entries: Ok(entries),
     }).await?;
     let mut pending = 1;
     while let Some(chunk) = receiver.recv().await {
-        if let Some(container) = chunk.container {
-            log::debug!("CONTAINER: {container:?}");
-            dir_printer.print_entry(&container);
-        }
-        let mut entries = chunk.entries;
+        // the first failure ends the walk; listings still running find the channel closed
+        let mut entries = chunk.entries?;
         entries.sort_unstable_by(by_text);
         let (files, subdirs) = split_files_subdirs(entries);
-        dir_printer.print_dir(&files);
         // recurse into subdirs
-        for entry in subdirs {
+        for entry in visit(chunk.container, files, subdirs) {
             pending += 1;
             let nexus = nexus.clone();
             let sender = sender.clone();
-            let repo_id = nexus_uri.repo_id.clone();
-            let remote_dir = entry.relative_path.clone();
+            let repo_id = repo_id.to_string();
             tokio::spawn(async move {
-                log::debug!("Listing for {remote_dir}");
-                match remote_sync::fetch_dir_for_recurse(&nexus, &repo_id, &entry.relative_path).await {
-                    Ok(entries) => {
-                        if let Err(e) = sender.send(DirChunk { container: Some(entry), entries }).await {
-                            log::error!("FATAL: Channel cannot send chunk: {e}");
-                        };
-                    }
-                    Err(e) => {
-                        log::error!("Failed to retrieve directory: {e}");
-                    }
+                log::debug!("Listing for {}", entry.relative_path);
+                let entries = remote_sync::fetch_dir_for_recurse(&nexus, &repo_id, &entry.relative_path).await
+                    .map_err(|e| e.context(format!("Failed to retrieve directory {}", entry.relative_path)));
+                if sender.send(DirChunk { container: Some(entry), entries }).await.is_err() {
+                    log::debug!("Walk already ended, dropping the listing");
                 }
             });
         }
@@ -64,7 +73,7 @@ pub async fn cmd_list(nexus: NexusClient, nexus_uri: &NexusRemoteUri, dir_printe
 #[derive(Debug)]
 struct DirChunk {
     container: Option<DirEntry>,
-    entries: Vec<DirEntry>,
+    entries: anyhow::Result<Vec<DirEntry>>,
 }
 
 fn split_files_subdirs(directory: Vec<DirEntry>) -> (Vec<DirEntry>, Vec<DirEntry>) {
@@ -86,19 +95,63 @@ fn by_text(a: &DirEntry, b: &DirEntry) -> Ordering {
 
 pub struct DirPrinter {
     pub format: DirFormat,
+    /// with structured output, entries are collected and printed at once by [DirPrinter::finish]
+    output: Option<OutputFormat>,
+    collected: Vec<DirEntry>,
+    /// in short format, print full remote URIs in this repository instead of bare names
+    repo_id: Option<String>,
 }
 
 impl DirPrinter {
-    pub fn print_dir(&self, entries: &[DirEntry]) {
+    /// `--format json` is the same as `--output json`; XML is available only from the server, in `staging activity`
+    pub fn new(format: DirFormat, output: Option<OutputFormat>) -> anyhow::Result<Self> {
+        let output = match format {
+            DirFormat::Json => output.or(Some(OutputFormat::Json)),
+            DirFormat::Xml => anyhow::bail!("Format xml is not supported for listing; use --output"),
+            DirFormat::Short | DirFormat::Long => output,
+        };
+        Ok(Self {
+            format,
+            output,
+            collected: Vec::new(),
+            repo_id: None,
+        })
+    }
+
+    /// Prints full remote URIs (`::/<repo_id>/<path>`) in short format, so that they can be passed to other commands.
+    pub fn with_full_uris(self, repo_id: &str) -> Self {
+        Self {
+            repo_id: Some(repo_id.to_string()),
+            ..self
+        }
+    }
+
+    pub fn print_dir(&mut self, entries: &[DirEntry]) {
         entries.iter().for_each(|entry| self.print_entry(entry));
     }
 
-    fn print_entry(&self, entry: &DirEntry) {
+    pub fn finish(&self) -> anyhow::Result<()> {
+        match self.output {
+            Some(output) => print_list(output, &self.collected),
+            None => Ok(()),
+        }
+    }
+
+    pub fn print_entry(&mut self, entry: &DirEntry) {
+        if self.output.is_some() {
+            self.collected.push(entry.clone());
+            return;
+        }
         match &self.format {
-            DirFormat::Short => {
-                let leaf = if entry.leaf { "" } else { "/" };
-                println!("{}{leaf}", entry.text)
-            }
+            // collected above, as structured output
+            DirFormat::Json | DirFormat::Xml => {}
+            DirFormat::Short => match &self.repo_id {
+                Some(repo_id) => println!("::/{repo_id}{}", entry.relative_path),
+                None => {
+                    let leaf = if entry.leaf { "" } else { "/" };
+                    println!("{}{leaf}", entry.text)
+                }
+            },
             DirFormat::Long => {
                 let size_or_dir = if entry.size_on_disk == -1 {
                     "/".to_string()
@@ -107,7 +160,6 @@ impl DirPrinter {
                 };
                 println!("{}\t{size_or_dir:>10}\t{}", entry.last_modified, &entry.relative_path[1..])
             }
-            format => panic!("Unknown format: {format:?}"),
         }
     }
 }
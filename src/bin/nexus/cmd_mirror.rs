@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use nexus_client::mirror::{mirror_download, mirror_upload, MirrorAction, MirrorOptions};
+
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{MirrorRecord, OutputFormat, print_list};
+
+/// Mirrors in direction given by which of `source` and `target` is the remote URI.
+pub async fn cmd_mirror(source: &str, target: &str, options: &MirrorOptions, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let changes = match (NexusRemoteUri::from_str(source), NexusRemoteUri::from_str(target)) {
+        (Ok(nexus_uri), Err(_)) => {
+            let remote_root = nexus_uri.repo_path_dir_or_err()?;
+            let nexus = crate::nexus_public_client()?;
+            mirror_download(&nexus, &nexus_uri.repo_id, remote_root, &PathBuf::from(target), options).await?
+        }
+        (Err(_), Ok(nexus_uri)) => {
+            let remote_root = nexus_uri.repo_path_dir_or_err()?;
+            let local_root = PathBuf::from(source);
+            if !local_root.is_dir() {
+                anyhow::bail!("Local directory expected: {source}");
+            }
+            let nexus = crate::nexus_client()?;
+            mirror_upload(&nexus, &nexus_uri.repo_id, remote_root, &local_root, options).await?
+        }
+        (Ok(_), Ok(_)) => anyhow::bail!("Mirroring between two remote trees is not supported, use `cp`"),
+        (Err(_), Err(_)) => anyhow::bail!("One of source and target must be a remote URI, like '::/releases/org/example/'"),
+    };
+    log::info!("{} changes{}", changes.len(), if options.dry_run { " would be made" } else { " made" });
+    let records: Vec<MirrorRecord> = changes.into_iter()
+        .map(|change| MirrorRecord {
+            action: match change.action {
+                MirrorAction::Download => "download",
+                MirrorAction::Upload => "upload",
+                MirrorAction::DeleteLocal => "delete-local",
+                MirrorAction::DeleteRemote => "delete-remote",
+            },
+            path: change.path,
+        })
+        .collect();
+    match output {
+        Some(output) => print_list(output, &records)?,
+        None if options.dry_run => {
+            for MirrorRecord { action, path } in records {
+                println!("{action}\t{path}");
+            }
+        }
+        None => {}
+    }
+    Ok(())
+}
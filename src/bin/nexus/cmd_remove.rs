@@ -0,0 +1,371 @@
+use std::collections::BTreeSet;
+
+use anyhow::Context;
+use futures_util::{stream, StreamExt};
+use reqwest::StatusCode;
+
+use nexus_client::{NexusClient, NexusRepository, remote_sync, StagingRepositories};
+use nexus_client::model::RepositoryState;
+use nexus_client::remote_sync::{glob_remote, is_glob};
+
+use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{OutputFormat, print_list, RemovedRecord};
+use crate::table::{Cell, human_size, print_aligned};
+
+/// Safety switches of `rm`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RemoveOptions {
+    /// allow removing a directory given without trailing slash
+    pub recursive: bool,
+    /// allow removing from staging repository which is not open, or is transitioning
+    pub force: bool,
+    /// do not ask for confirmation
+    pub yes: bool,
+    /// only show what would be removed
+    pub dry_run: bool,
+    /// how many paths are removed concurrently
+    pub jobs: usize,
+}
+
+/// A path to remove, with the count and total size of files under it
+struct Target {
+    nexus_uri: NexusRemoteUri,
+    files: usize,
+    size: u64,
+}
+
+pub async fn cmd_remove(nexus: &NexusClient, nexus_uris: &[NexusRemoteUri], options: RemoveOptions, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let mut expanded = Vec::new();
+    for nexus_uri in nexus_uris {
+        expanded.extend(expand(nexus, nexus_uri).await?);
+    }
+    remove_paths(nexus, &expanded, options, output).await
+}
+
+/// Removes the paths as they are, without expanding patterns - after checking the state of staging repositories,
+/// and confirmation by user.
+pub async fn remove_paths(nexus: &NexusClient, nexus_uris: &[NexusRemoteUri], options: RemoveOptions, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    let repo_ids: BTreeSet<&str> = nexus_uris.iter().map(|nexus_uri| nexus_uri.repo_id.as_str()).collect();
+    for repo_id in repo_ids {
+        check_staging_state(nexus, repo_id, options.force).await?;
+    }
+    let mut targets = Vec::new();
+    for nexus_uri in nexus_uris {
+        let (files, size) = measure(nexus, nexus_uri, options.recursive).await?;
+        targets.push(Target { nexus_uri: nexus_uri.clone(), files, size });
+    }
+    if targets.is_empty() {
+        log::info!("Nothing matches, nothing to remove");
+        return Ok(());
+    }
+    let summary = summary(&targets);
+    if options.dry_run {
+        print_preview(&targets);
+        log::info!("Would remove {summary}");
+        return Ok(());
+    }
+    if !options.yes {
+        // stdout is reserved for the result
+        for target in &targets {
+            eprintln!("{}\t{} files\t{}", target.nexus_uri, target.files, human_size(target.size));
+        }
+        if !crate::confirm(&format!("Remove {summary}?"))? {
+            anyhow::bail!("Cancelled by user");
+        }
+    }
+    let records = remove_targets(nexus, targets, options.jobs).await;
+    if let Some(output) = output {
+        print_list(output, &records)?;
+    }
+    let failed = records.iter().filter(|record| record.error.is_some()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} paths could not be removed", records.len());
+    }
+    Ok(())
+}
+
+/// Removes each target, `jobs` at once; failures are reported in the records, and do not stop removing the others.
+async fn remove_targets(nexus: &NexusClient, targets: Vec<Target>, jobs: usize) -> Vec<RemovedRecord> {
+    stream::iter(targets)
+        .map(|target| async move {
+            let request = NexusRepository::nexus_readwrite(&target.nexus_uri.repo_id)
+                .delete(&target.nexus_uri.repo_path);
+            let result = match nexus.execute(request).await {
+                Ok(response) => response.check().await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            let error = match result {
+                Ok(()) => {
+                    log::warn!("Removed: {}", target.nexus_uri);
+                    None
+                }
+                Err(e) => {
+                    log::error!("Failed to remove {}: {e}", target.nexus_uri);
+                    Some(e.to_string())
+                }
+            };
+            RemovedRecord { remote_uri: target.nexus_uri.to_string(), error }
+        })
+        .buffer_unordered(jobs.max(1))
+        .collect().await
+}
+
+/// Like `2 paths (5 files, 1.2M)`
+fn summary(targets: &[Target]) -> String {
+    let files: usize = targets.iter().map(|target| target.files).sum();
+    let size: u64 = targets.iter().map(|target| target.size).sum();
+    format!("{} paths ({files} files, {})", targets.len(), human_size(size))
+}
+
+fn print_preview(targets: &[Target]) {
+    let rows = targets.iter()
+        .map(|target| vec![
+            Cell::from(target.nexus_uri.to_string()),
+            Cell::from(format!("{} files", target.files)),
+            Cell::from(human_size(target.size)),
+        ])
+        .collect();
+    print_aligned(None, rows, &[]);
+}
+
+/// Expands glob patterns in the path by listing remote directories; paths without patterns are passed as they are.
+/// Directories matched by a pattern without trailing slash lose their slash, so that they need `--recursive`.
+async fn expand(nexus: &NexusClient, nexus_uri: &NexusRemoteUri) -> anyhow::Result<Vec<NexusRemoteUri>> {
+    if !is_glob(&nexus_uri.repo_path) {
+        return Ok(vec![nexus_uri.clone()]);
+    }
+    let entries = glob_remote(nexus, &nexus_uri.repo_id, &nexus_uri.repo_path).await?;
+    log::debug!("{nexus_uri} matches {} paths", entries.len());
+    Ok(entries.into_iter()
+        .map(|entry| {
+            let repo_path = if nexus_uri.is_dir() {
+                entry.relative_path
+            } else {
+                entry.relative_path.trim_end_matches('/').to_string()
+            };
+            NexusRemoteUri { repo_id: nexus_uri.repo_id.clone(), repo_path }
+        })
+        .collect())
+}
+
+/// Refuses to modify a staging repository, unless it is open and not transitioning.
+/// Repositories unknown to staging (HTTP 404) are not checked; any other failure is an error.
+async fn check_staging_state(nexus: &NexusClient, repo_id: &str, force: bool) -> anyhow::Result<()> {
+    let repo_id = repo_id.strip_prefix("@staging:").unwrap_or(repo_id);
+    let response = nexus.execute(StagingRepositories::get(repo_id)).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        log::debug!("{repo_id} is not a staging repository");
+        return Ok(());
+    }
+    let repo = response.parsed().await
+        .with_context(|| format!("Cannot check state of staging repository {repo_id}"))?;
+    // missing values are not trusted to mean open and idle
+    if repo.state() == Some(RepositoryState::Open) && repo.transitioning == Some(false) {
+        return Ok(());
+    }
+    let state = match (repo.state(), repo.transitioning) {
+        (None, _) => "in unknown state".to_string(),
+        (Some(_), Some(true)) => format!("{} (transitioning)", repo.repository_type),
+        (Some(_), Some(false)) => repo.repository_type.clone(),
+        (Some(_), None) => format!("{} (transitioning unknown)", repo.repository_type),
+    };
+    if !force {
+        anyhow::bail!("Staging repository {repo_id} is {state}; use --force to remove from it anyway");
+    }
+    log::warn!("Staging repository {repo_id} is {state}, removing anyway");
+    Ok(())
+}
+
+/// Counts files to be removed, and their total size.
+/// A directory must be given with trailing slash, unless `recursive` - so that the caller proves to know it is a directory.
+async fn measure(nexus: &NexusClient, nexus_uri: &NexusRemoteUri, recursive: bool) -> anyhow::Result<(usize, u64)> {
+    let dir = if nexus_uri.is_dir() {
+        nexus_uri.repo_path.clone()
+    } else {
+        let (parent, name) = nexus_uri.repo_path.rsplit_once('/').unwrap_or(("", &nexus_uri.repo_path));
+        let siblings = remote_sync::fetch_dir(nexus, &nexus_uri.repo_id, &format!("{parent}/")).await?;
+        match siblings.into_iter().find(|entry| entry.text == name) {
+            None => anyhow::bail!("Not found: {nexus_uri}"),
+            Some(entry) if entry.leaf => return Ok((1, entry.size_on_disk.max(0) as u64)),
+            Some(_) if !recursive => anyhow::bail!("{nexus_uri} is a directory; add trailing slash, or use --recursive"),
+            Some(_) => format!("{}/", nexus_uri.repo_path),
+        }
+    };
+    let files = remote_sync::list_remote_files(nexus, &nexus_uri.repo_id, &dir).await?;
+    let size = files.values().map(|entry| entry.size_on_disk.max(0) as u64).sum();
+    Ok((files.len(), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nexus_uri::NexusRemoteUri;
+    use crate::test_server::{Repositories, Request, Response, TestServer};
+
+    use super::{check_staging_state, cmd_remove, expand, measure, remove_paths, remove_targets, RemoveOptions, summary, Target};
+
+    const FILES: [(&str, &str); 3] = [
+        ("org/example/demo/1.0/demo-1.0.pom", "<project/>"),
+        ("org/example/demo/1.0/demo-1.0.jar", "demo 1.0"),
+        ("org/example/demo/1.1/demo-1.1.jar", "demo 1.1"),
+    ];
+
+    /// Serves the repositories, with `orgexample-1001` being a staging repository in given state
+    fn staging(repositories: &Repositories, state: &'static str, transitioning: bool) -> impl Fn(&Request) -> Response + Send + Sync + 'static {
+        let handler = repositories.handler();
+        move |request| match request.path.strip_prefix("/service/local/staging/repository/") {
+            Some("orgexample-1001") => Response::ok(serde_json::json!({
+                "repositoryId": "orgexample-1001",
+                "type": state,
+                "transitioning": transitioning,
+            }).to_string().as_bytes()).header("Content-Type", "application/json"),
+            Some(_) => Response::status(404),
+            None => handler(request),
+        }
+    }
+
+    fn uri(uri: &str) -> NexusRemoteUri {
+        uri.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_check_staging_state() -> anyhow::Result<()> {
+        let repositories = Repositories::default();
+        for (state, transitioning, allowed) in [("open", false, true), ("open", true, false), ("closed", false, false), ("released", false, false)] {
+            let server = TestServer::start(staging(&repositories, state, transitioning)).await?;
+            let nexus = server.client()?;
+            let result = check_staging_state(&nexus, "orgexample-1001", false).await;
+            assert_eq!(result.is_ok(), allowed, "{state}, transitioning: {transitioning}");
+            if !allowed {
+                let message = result.unwrap_err().to_string();
+                assert!(message.contains("use --force"), "{message}");
+            }
+            check_staging_state(&nexus, "@staging:orgexample-1001", true).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_not_staging() -> anyhow::Result<()> {
+        let server = TestServer::start(staging(&Repositories::default(), "closed", false)).await?;
+        check_staging_state(&server.client()?, "releases", false).await?;
+        // failures other than 404 are not taken for "not staging"
+        let server = TestServer::start(|_| Response::status(500)).await?;
+        assert!(check_staging_state(&server.client()?, "releases", false).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_measure() -> anyhow::Result<()> {
+        let server = TestServer::start(Repositories::default().with("releases", &FILES).handler()).await?;
+        let nexus = server.client()?;
+
+        assert_eq!(measure(&nexus, &uri("::/releases/org/example/demo/1.0/demo-1.0.jar"), false).await?, (1, 8));
+        assert_eq!(measure(&nexus, &uri("::/releases/org/example/demo/"), false).await?, (3, 26));
+        let message = measure(&nexus, &uri("::/releases/org/example/demo"), false).await.unwrap_err().to_string();
+        assert!(message.contains("is a directory"), "{message}");
+        assert_eq!(measure(&nexus, &uri("::/releases/org/example/demo"), true).await?, (3, 26));
+        assert!(measure(&nexus, &uri("::/releases/org/example/missing.jar"), false).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary() {
+        let targets = vec![
+            Target { nexus_uri: uri("::/releases/org/example/demo/1.0/"), files: 2, size: 18 },
+            Target { nexus_uri: uri("::/releases/org/example/big.jar"), files: 1, size: 3 * 1024 * 1024 },
+        ];
+        assert_eq!(summary(&targets), "2 paths (3 files, 3.0M)");
+    }
+
+    #[tokio::test]
+    async fn test_remove_from_closed_staging() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("orgexample-1001", &FILES);
+        let server = TestServer::start(staging(&repositories, "closed", false)).await?;
+        let nexus = server.client()?;
+        let targets = [uri("::/orgexample-1001/org/example/demo/1.1/")];
+        let options = RemoveOptions { yes: true, jobs: 1, ..RemoveOptions::default() };
+
+        let refused = remove_paths(&nexus, &targets, options, None).await;
+        let requests_before_force = server.requests();
+        remove_paths(&nexus, &targets, RemoveOptions { force: true, ..options }, None).await?;
+
+        assert!(refused.is_err());
+        assert!(requests_before_force.iter().all(|request| request.method != "DELETE"));
+        assert_eq!(repositories.paths("orgexample-1001").iter().filter(|path| !path.ends_with('/')).count(), 2);
+        Ok(())
+    }
+
+    fn uris(nexus_uris: &[NexusRemoteUri]) -> Vec<String> {
+        nexus_uris.iter().map(|nexus_uri| nexus_uri.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_expand() -> anyhow::Result<()> {
+        let server = TestServer::start(Repositories::default().with("releases", &FILES).handler()).await?;
+        let nexus = server.client()?;
+
+        assert_eq!(uris(&expand(&nexus, &uri("::/releases/org/example/*/1.*/*.jar")).await?),
+                   vec!["::/releases/org/example/demo/1.0/demo-1.0.jar", "::/releases/org/example/demo/1.1/demo-1.1.jar"]);
+        // matched directories keep trailing slash only when the pattern has it
+        assert_eq!(uris(&expand(&nexus, &uri("::/releases/org/example/demo/1.*/")).await?),
+                   vec!["::/releases/org/example/demo/1.0/", "::/releases/org/example/demo/1.1/"]);
+        assert_eq!(uris(&expand(&nexus, &uri("::/releases/org/example/demo/1.*")).await?),
+                   vec!["::/releases/org/example/demo/1.0", "::/releases/org/example/demo/1.1"]);
+        assert_eq!(uris(&expand(&nexus, &uri("::/releases/org/example/demo/2.*")).await?), Vec::<String>::new());
+        // no pattern, no listing
+        let requests = server.requests().len();
+        assert_eq!(uris(&expand(&nexus, &uri("::/releases/org/example/demo/1.0/")).await?), vec!["::/releases/org/example/demo/1.0/"]);
+        assert_eq!(server.requests().len(), requests);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_patterns() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("releases", &FILES);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+        let options = RemoveOptions { yes: true, jobs: 2, ..RemoveOptions::default() };
+
+        cmd_remove(&nexus, &[uri("::/releases/org/example/*/2.*/")], options, None).await?;
+        cmd_remove(&nexus, &[uri("::/releases/org/example/demo/*/*.jar")], RemoveOptions { dry_run: true, ..options }, None).await?;
+        let requests_before_removal = server.requests();
+        cmd_remove(&nexus, &[uri("::/releases/org/example/demo/*/*.jar")], options, None).await?;
+
+        assert!(requests_before_removal.iter().all(|request| request.method != "DELETE"));
+        assert_eq!(repositories.paths("releases").iter().filter(|path| !path.ends_with('/')).collect::<Vec<_>>(),
+                   vec!["org/example/demo/1.0/demo-1.0.pom"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_partial_failure() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("releases", &FILES);
+        let handler = repositories.handler();
+        let server = TestServer::start(move |request| match request.method.as_str() {
+            "DELETE" if request.path.contains("/1.0/") => Response::status(500),
+            _ => handler(request),
+        }).await?;
+        let nexus = server.client()?;
+        let options = RemoveOptions { yes: true, jobs: 1, ..RemoveOptions::default() };
+
+        let result = cmd_remove(&nexus, &[uri("::/releases/org/example/demo/*/")], options, None).await;
+        let remaining = repositories.paths("releases");
+        repositories.put("releases", "org/example/demo/1.1/demo-1.1.jar", b"demo 1.1");
+        let targets = vec![
+            Target { nexus_uri: uri("::/releases/org/example/demo/1.0/"), files: 2, size: 18 },
+            Target { nexus_uri: uri("::/releases/org/example/demo/1.1/"), files: 1, size: 8 },
+        ];
+        let mut records = remove_targets(&nexus, targets, 2).await;
+        records.sort_by(|a, b| a.remote_uri.cmp(&b.remote_uri));
+
+        // the failure does not stop removing the others
+        assert_eq!(result.unwrap_err().to_string(), "1 of 2 paths could not be removed");
+        assert!(remaining.iter().any(|path| path.contains("1.0")));
+        assert!(!remaining.iter().any(|path| path.contains("1.1")));
+        assert_eq!(records[0].remote_uri, "::/releases/org/example/demo/1.0/");
+        assert!(records[0].error.as_ref().is_some_and(|error| error.contains("500")), "{:?}", records[0].error);
+        assert_eq!(records[1].remote_uri, "::/releases/org/example/demo/1.1/");
+        assert_eq!(records[1].error, None);
+        Ok(())
+    }
+}
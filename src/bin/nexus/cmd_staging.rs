@@ -1,243 +1,561 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 use clap::Subcommand;
 
 use nexus_client::{StagingProfiles, StagingRepositories};
-use nexus_client::model::{StagingProfile, StagingProfileRepository};
+use nexus_client::bundle::pack_bundle;
+use nexus_client::model::{RepositoryState, StagingProfile, StagingProfileRepository};
 
 use crate::DirFormat;
+use crate::output::{OutputFormat, print_item, print_list, StagingActionRecord};
+use crate::table::{Cell, Color, Column, eprint_table, print_aligned, print_table, relative_age, TableOptions};
+use crate::selector::{ProfileSelector, RepoSelector, resolve_profile_id, resolve_repository_id, resolve_repository_ids};
 
-pub async fn cmd_staging(staging_command: StagingCommands) -> anyhow::Result<()> {
+pub async fn cmd_staging(staging_command: StagingCommands, output: Option<OutputFormat>) -> anyhow::Result<()> {
     match staging_command {
         StagingCommands::Profile { profile } => {
             let nexus = crate::nexus_client()?;
+            let profile = resolve_profile_id(&nexus, profile.as_ref(), None).await?;
             let response = nexus.execute(StagingProfiles::get(&profile)).await?;
             let profile = response.parsed().await?;
-            let StagingProfile { resource_uri, id, name, repository_template_id, repository_type, repository_target_id, in_progress, order, deploy_uri, target_groups, finish_notify_roles, promotion_notify_roles, drop_notify_roles, close_rule_sets, promote_rule_sets, promotion_target_repository, mode, finish_notify_creator, promotion_notify_creator, drop_notify_creator, auto_staging_disabled, repositories_searchable, properties } = profile;
-            println!(r##"id={id}
-name={name}
-mode={mode}
-auto_staging_disabled={auto_staging_disabled}
-close_rule_sets={close_rule_sets:?}
-deploy_uri={deploy_uri}
-drop_notify_creator={drop_notify_creator}
-drop_notify_roles={drop_notify_roles:?}
-finish_notify_creator={finish_notify_creator}
-finish_notify_roles={finish_notify_roles:?}
-in_progress={in_progress}
-order={order}
-promotion_target_repository={promotion_target_repository}
-properties={properties:?}
-repository_target_id={repository_target_id}
-repository_template_id={repository_template_id}
-repositories_searchable={repositories_searchable}
-promote_rule_sets={promote_rule_sets:?}
-promotion_notify_creator={promotion_notify_creator}
-promotion_notify_roles={promotion_notify_roles:?}
-repository_type={repository_type}
-resource_uri={resource_uri}
-target_groups={target_groups:?}
-"##);
+            if let Some(output) = output {
+                return print_item(output, &profile);
+            }
+            print_profile(profile);
         }
-        StagingCommands::Profiles => {
+        StagingCommands::Profiles { table } => {
             let nexus = crate::nexus_client()?;
             let response = nexus.execute(StagingProfiles::list()).await?;
-            let list = response.parsed().await?;
-            for profile in list {
-                println!("{} {} mode={} target={}", profile.id, profile.name, profile.mode, profile.promotion_target_repository);
-                log::debug!("{profile:?}");
+            let mut list = response.parsed().await?;
+            if let Some(output) = output {
+                return print_list(output, &list);
             }
+            print_table(&mut list, &profile_columns(), PROFILE_COLUMNS, &table)?;
         }
-        StagingCommands::Repos { format } => {
+        StagingCommands::Repos { format, table, selectors } => {
             let nexus = crate::nexus_client()?;
+            if format == DirFormat::Xml && output.is_none() {
+                if !selectors.is_empty() {
+                    anyhow::bail!("Format xml shows all repositories, it cannot be combined with selectors; use --output");
+                }
+                let response = nexus.execute(StagingRepositories::list_xml()).await?;
+                println!("{}", response.text().await?);
+                return Ok(());
+            }
             let response = nexus.execute(StagingRepositories::list()).await?;
             let list = response.parsed().await?;
-            match format {
-                DirFormat::Short => {
-                    for StagingProfileRepository { repository_id, repository_type, transitioning, description, .. } in list {
-                        let t = if transitioning { "transitioning" } else { "ready" };
-                        println!("{repository_id}\t{repository_type}\t{t}\t{description}");
-                    }
-                }
-                DirFormat::Long => {
-                    for repo in list {
-                        // TODO not sure if this is a good choice for the "long" format
-                        println!("{repo:?}");
-                    }
+            let list = if selectors.is_empty() {
+                list
+            } else {
+                let mut selected = Vec::new();
+                for selector in &selectors {
+                    selected.extend(selector.select(&list)?.into_iter().cloned());
                 }
-                _ => todo!()
+                selected
+            };
+            let output = match format {
+                DirFormat::Json => output.or(Some(OutputFormat::Json)),
+                _ => output,
+            };
+            if let Some(output) = output {
+                return print_list(output, &list);
             }
+            let mut list = list;
+            let defaults = match format {
+                DirFormat::Long => REPO_COLUMNS_LONG,
+                _ => REPO_COLUMNS,
+            };
+            print_table(&mut list, &repo_columns(), defaults, &table)?;
         }
 
         StagingCommands::Repo { repository_id } => {
             let nexus = crate::nexus_client()?;
+            let repository_id = resolve_repository_id(&nexus, &repository_id).await?;
             let response = nexus.execute(StagingRepositories::get(&repository_id)).await?;
             let repo = response.parsed().await?;
-            log::info!("{repo:?}");
+            print_item(output.unwrap_or(OutputFormat::Yaml), &repo)?;
         }
 
         StagingCommands::RepoActivity { repository_id, format } => {
             let nexus = crate::nexus_client()?;
-            if format == DirFormat::Json {
-                let request = StagingRepositories::activity(&repository_id);
-                let response = nexus.execute(request).await?;
-                let text = response.text().await?;
-                println!("{text}");
-            } else if format == DirFormat::Xml {
-                let request = StagingRepositories::activity_xml(&repository_id);
-                let response = nexus.execute(request).await?;
-                let text = response.text().await?;
-                println!("{text}");
-            } else {
+            let repository_id = resolve_repository_id(&nexus, &repository_id).await?;
+            if let Some(output) = output {
                 let request = StagingRepositories::activity(&repository_id);
-                let response = nexus.execute(request).await?;
-                let activities = response.parsed().await?;
-                match format {
-                    DirFormat::Short => {
-                        for activity in activities {
-                            let last_event_name = match activity.events.last() {
-                                None => "?",
-                                Some(event) => &event.name
-                            };
-                            println!("{}: {last_event_name}", activity.name);
-                        }
+                let activities = nexus.execute(request).await?.parsed().await?;
+                return print_list(output, &activities);
+            }
+            match format {
+                DirFormat::Json => {
+                    let request = StagingRepositories::activity(&repository_id);
+                    let response = nexus.execute(request).await?;
+                    let text = response.text().await?;
+                    println!("{text}");
+                }
+                DirFormat::Xml => {
+                    let request = StagingRepositories::activity_xml(&repository_id);
+                    let response = nexus.execute(request).await?;
+                    let text = response.text().await?;
+                    println!("{text}");
+                }
+                DirFormat::Short => {
+                    let request = StagingRepositories::activity(&repository_id);
+                    let activities = nexus.execute(request).await?.parsed().await?;
+                    for activity in activities {
+                        let last_event_name = match activity.events.last() {
+                            None => "?",
+                            Some(event) => &event.name
+                        };
+                        println!("{}: {last_event_name}", activity.name);
                     }
-                    DirFormat::Long => {
-                        println!("Activities for staging repository '{repository_id}'");
-                        for activity in activities {
-                            println!("\nactivity '{}' for staging repository - started {}, stopped {}", activity.name, activity.started, activity.stopped);
-                            for event in &activity.events {
-                                println!("* {} [{}] {}", event.timestamp, event.severity, event.name);
-                                for prop in &event.properties {
-                                    println!("    {}:{}", prop.name, prop.value);
-                                }
+                }
+                DirFormat::Long => {
+                    let request = StagingRepositories::activity(&repository_id);
+                    let activities = nexus.execute(request).await?.parsed().await?;
+                    println!("Activities for staging repository '{repository_id}'");
+                    for activity in activities {
+                        println!("\nactivity '{}' for staging repository - started {}, stopped {}", activity.name, activity.started, activity.stopped);
+                        for event in &activity.events {
+                            println!("* {} [{}] {}", event.timestamp, event.severity, event.name);
+                            for prop in &event.properties {
+                                println!("    {}:{}", prop.name, prop.value);
                             }
                         }
                     }
-                    _ => todo!()
                 }
             }
         }
-        StagingCommands::RepoStart { profile_id, format, description } => {
+        StagingCommands::RepoStart { profile_id, artifact, format, description } => {
+            // checked before the repository is created
+            if format == DirFormat::Xml {
+                anyhow::bail!("Format xml is not supported by `staging start`; use --output");
+            }
             let nexus = crate::nexus_client()?;
+            let profile_id = resolve_profile_id(&nexus, profile_id.as_ref(), artifact.as_deref()).await?;
             let request = StagingProfiles::start(&profile_id, &description.unwrap_or("".to_string()));
             let response = nexus.execute(request).await?;
             let response = response.parsed().await?;
             let staged_repo_id = response.data.staged_repository_id.ok_or(anyhow::anyhow!("No ID returned"))?;
-            match format {
-                DirFormat::Short => {
+            let output = match format {
+                DirFormat::Json => output.or(Some(OutputFormat::Json)),
+                _ => output,
+            };
+            if let Some(output) = output {
+                return print_item(output, &StagingActionRecord {
+                    action: "start",
+                    repository_id: staged_repo_id,
+                    description: response.data.description,
+                });
+            }
+            if format == DirFormat::Long {
+                println!("{staged_repo_id}\t{}", response.data.description.unwrap_or("".to_string()));
+            } else {
+                println!("{staged_repo_id}");
+            }
+        }
+        StagingCommands::Bundle { local_path } => {
+            let (bundle, file_name) = if local_path.is_dir() {
+                (pack_bundle(&local_path)?, "bundle.jar".to_string())
+            } else {
+                let file_name = local_path.file_name().map_or("bundle.jar".into(), |s| s.to_string_lossy().to_string());
+                (std::fs::read(&local_path)?, file_name)
+            };
+            let nexus = crate::nexus_client()?;
+            log::info!("uploading bundle from {} ({} bytes)", local_path.display(), bundle.len());
+            let response = nexus.upload_bundle(bundle, &file_name).await?;
+            if let Some(output) = output {
+                print_actions(output, "bundle", &response.repository_ids(), "")?;
+            } else {
+                for staged_repo_id in response.repository_ids() {
                     println!("{staged_repo_id}");
                 }
-                DirFormat::Long => {
-                    println!("{staged_repo_id}\t{}", response.data.description.unwrap_or("".to_string()));
-                }
-                _ => panic!("Unsupported format: {format:?}"),
             }
         }
-        StagingCommands::RepoDescribe { profile_id, repository_id, description } => {
+        StagingCommands::RepoDescribe { repository_id, description } => {
             let nexus = crate::nexus_client()?;
-            let description = description.as_ref().map_or("", |s| s.as_str());
-            let request = StagingProfiles::describe(&profile_id, &repository_id, description);
-            let response = nexus.execute(request).await?;
-            let s = response.text().await?;
-            println!("{s:?}");
+            let repository_id = resolve_repository_id(&nexus, &repository_id).await?;
+            let repo = nexus.execute(StagingRepositories::get(&repository_id)).await?.parsed().await?;
+            // Nexus records descriptions only with transitions; closing is the one which keeps the repository around
+            if repo.state() != Some(RepositoryState::Open) {
+                anyhow::bail!("Staging repository {repository_id} is {}, only open one can be described; use --description of promote or drop",
+                    repo.repository_type);
+            }
+            let repository_ids = [repository_id];
+            let response = nexus.execute(StagingRepositories::bulk_close(&repository_ids, &description)).await?;
+            response.check().await?;
+            log::info!("Staging repository {} is being finished, described as: {description}", repository_ids[0]);
+            if let Some(output) = output {
+                print_actions(output, "describe", &repository_ids, &description)?;
+            }
         }
-        StagingCommands::RepoFinish { profile_id, repository_id, description } => {
+        StagingCommands::RepoFinish { repository_ids, validate, description } => {
+            if let Some(dir) = validate {
+                crate::cmd_validate::cmd_validate(&dir, None)?;
+            }
             let nexus = crate::nexus_client()?;
+            let repository_ids = resolve_repository_ids(&nexus, &repository_ids).await?;
             let description = description.as_ref().map_or("", |s| s.as_str());
-            let request = StagingProfiles::finish(&profile_id, &repository_id, description);
-            let response = nexus.execute(request).await?;
-            let s = response.text().await?;
-            println!("{s:?}");
+            let response = nexus.execute(StagingRepositories::bulk_close(&repository_ids, description)).await?;
+            response.check().await?;
+            log::info!("Staging repositories are being finished: {}", repository_ids.join(" "));
+            if let Some(output) = output {
+                print_actions(output, "finish", &repository_ids, description)?;
+            }
         }
 
-        StagingCommands::RepoPromote { profile_id, repository_id } => {
+        StagingCommands::RepoPromote { repository_ids, description } => {
             let nexus = crate::nexus_client()?;
-            let request = StagingProfiles::promote(&profile_id, &repository_id);
-            let response = nexus.execute(request).await?;
-            let s = response.text().await?;
-            println!("{s:?}");
+            let repository_ids = resolve_repository_ids(&nexus, &repository_ids).await?;
+            let description = description.as_ref().map_or("", |s| s.as_str());
+            let response = nexus.execute(StagingRepositories::bulk_promote(&repository_ids, description)).await?;
+            response.check().await?;
+            log::info!("Staging repositories are being promoted: {}", repository_ids.join(" "));
+            if let Some(output) = output {
+                print_actions(output, "promote", &repository_ids, description)?;
+            }
         }
-        StagingCommands::RepoDrop { profile_id, repository_ids } => {
-            if repository_ids.is_empty() {
-                anyhow::bail!("Nothing to drop!");
+        StagingCommands::Gc { older_than, state, desc_pattern, yes, table } => {
+            let nexus = crate::nexus_client()?;
+            let response = nexus.execute(StagingRepositories::list()).await?;
+            let list = response.parsed().await?;
+            let cutoff = Utc::now() - chrono::Duration::from_std(older_than)?;
+            let mut stale = select_stale(list, cutoff, state.as_ref(), desc_pattern.as_ref());
+            if stale.is_empty() {
+                log::info!("No stale staging repositories found");
+                return Ok(());
+            }
+            if output.is_some() {
+                // structured output must stay clean
+                eprint_table(&mut stale, &repo_columns(), REPO_COLUMNS, &table)?;
+            } else {
+                print_table(&mut stale, &repo_columns(), REPO_COLUMNS, &table)?;
             }
+            if !yes && !crate::confirm(&format!("Drop {} staging repositories?", stale.len()))? {
+                anyhow::bail!("Cancelled by user");
+            }
+            let repository_ids: Vec<String> = stale.into_iter().map(|repo| repo.repository_id).collect();
+            let description = "Stale staging repository cleanup";
+            let response = nexus.execute(StagingRepositories::bulk_drop(&repository_ids, description)).await?;
+            response.check().await?;
+            log::warn!("Stale staging repositories were successfully dropped: {}", repository_ids.join(" "));
+            if let Some(output) = output {
+                print_actions(output, "drop", &repository_ids, description)?;
+            }
+        }
+        StagingCommands::RepoDrop { repository_ids, description } => {
             let nexus = crate::nexus_client()?;
-            for repository_id in repository_ids {
-                let response = nexus.execute(StagingProfiles::drop(&profile_id, &repository_id)).await?;
-                response.check().await?;
-                log::warn!("Staging repository with profile '{profile_id}' was successfully dropped: {repository_id}");
+            let repository_ids = resolve_repository_ids(&nexus, &repository_ids).await?;
+            let description = description.as_ref().map_or("", |s| s.as_str());
+            let response = nexus.execute(StagingRepositories::bulk_drop(&repository_ids, description)).await?;
+            response.check().await?;
+            log::warn!("Staging repositories were successfully dropped: {}", repository_ids.join(" "));
+            if let Some(output) = output {
+                print_actions(output, "drop", &repository_ids, description)?;
             }
         }
     }
     Ok(())
 }
 
+/// Repositories neither created nor updated since `cutoff`, optionally only in given `state` and with matching description.
+/// Those without any timestamp are never selected, as their age is unknown.
+fn select_stale(list: Vec<StagingProfileRepository>, cutoff: DateTime<Utc>, state: Option<&RepositoryState>, desc_pattern: Option<&glob::Pattern>) -> Vec<StagingProfileRepository> {
+    list.into_iter()
+        .filter(|repo| repo.updated_at().max(repo.created_at()).is_some_and(|last_change| last_change < cutoff))
+        .filter(|repo| state.is_none_or(|state| repo.state().as_ref() == Some(state)))
+        .filter(|repo| desc_pattern.is_none_or(|pattern| pattern.matches(&repo.description)))
+        .collect()
+}
+
+/// Accepts only states known to us, as any other would select nothing.
+fn parse_state(s: &str) -> anyhow::Result<RepositoryState> {
+    match RepositoryState::from(s.to_string()) {
+        RepositoryState::Other(_) => anyhow::bail!("Unknown state '{s}', expected one of: open, closed, released"),
+        state => Ok(state),
+    }
+}
+
+fn print_actions(output: OutputFormat, action: &'static str, repository_ids: &[String], description: &str) -> anyhow::Result<()> {
+    let records: Vec<StagingActionRecord> = repository_ids.iter()
+        .map(|repository_id| StagingActionRecord {
+            action,
+            repository_id: repository_id.clone(),
+            description: (!description.is_empty()).then(|| description.to_string()),
+        })
+        .collect();
+    print_list(output, &records)
+}
+
+/// Columns of `staging repos` table, shown by default
+const REPO_COLUMNS: &[&str] = &["id", "state", "profile", "updated", "description"];
+/// Columns of `staging repos --format long`
+const REPO_COLUMNS_LONG: &[&str] = &["id", "state", "profile", "user", "policy", "created", "updated", "release", "description"];
+const PROFILE_COLUMNS: &[&str] = &["id", "name", "mode", "target"];
+
+fn repo_columns() -> Vec<Column<StagingProfileRepository>> {
+    vec![
+        Column::new("id", |repo| repo.repository_id.clone().into()),
+        Column::new("state", state_cell),
+        Column::new("profile", |repo| repo.profile_name.clone().into()),
+        Column::new("user", |repo| repo.user_id.clone().into()),
+        Column::new("policy", |repo| repo.policy.clone().into()),
+        Column::new("created", |repo: &StagingProfileRepository| relative_age(repo.created_at()).into())
+            .compare(|a, b| a.created_timestamp.cmp(&b.created_timestamp)),
+        Column::new("updated", |repo: &StagingProfileRepository| relative_age(repo.updated_at()).into())
+            .compare(|a, b| a.updated_timestamp.cmp(&b.updated_timestamp)),
+        Column::new("release", |repo| repo.release_repository_id.clone().into()),
+        Column::new("description", |repo: &StagingProfileRepository| repo.description.clone().into()).truncate(),
+    ]
+}
+
+fn state_cell(repo: &StagingProfileRepository) -> Cell {
+    if repo.transitioning == Some(true) {
+        return Cell { text: format!("{}…", repo.repository_type), color: Some(Color::Magenta) };
+    }
+    let color = match repo.state() {
+        Some(RepositoryState::Open) => Some(Color::Green),
+        Some(RepositoryState::Closed) => Some(Color::Yellow),
+        Some(RepositoryState::Released) => Some(Color::Blue),
+        Some(RepositoryState::Other(_)) => Some(Color::Red),
+        None => return Cell::from("?".to_string()),
+    };
+    Cell { text: repo.repository_type.clone(), color }
+}
+
+fn profile_columns() -> Vec<Column<StagingProfile>> {
+    vec![
+        Column::new("id", |profile| profile.id.clone().into()),
+        Column::new("name", |profile| profile.name.clone().into()),
+        Column::new("mode", |profile| profile.mode.clone().into()),
+        Column::new("target", |profile| profile.promotion_target_repository.clone().into()),
+        Column::new("order", |profile: &StagingProfile| profile.order.to_string().into())
+            .compare(|a, b| a.order.cmp(&b.order)),
+        Column::new("deploy_uri", |profile: &StagingProfile| profile.deploy_uri.clone().into()).truncate(),
+    ]
+}
+
+/// Prints the profile as aligned `key  value` lines
+fn print_profile(profile: StagingProfile) {
+    let StagingProfile { resource_uri, id, name, repository_template_id, repository_type, repository_target_id, in_progress, order, deploy_uri, target_groups, finish_notify_roles, promotion_notify_roles, drop_notify_roles, close_rule_sets, promote_rule_sets, promotion_target_repository, mode, finish_notify_creator, promotion_notify_creator, drop_notify_creator, auto_staging_disabled, repositories_searchable, properties, extra } = profile;
+    let mut fields: Vec<(String, String)> = vec![
+        ("id".into(), id),
+        ("name".into(), name),
+        ("mode".into(), mode.to_string()),
+        ("promotion_target_repository".into(), promotion_target_repository),
+        ("deploy_uri".into(), deploy_uri),
+        ("order".into(), order.to_string()),
+        ("in_progress".into(), in_progress.to_string()),
+        ("auto_staging_disabled".into(), auto_staging_disabled.to_string()),
+        ("repositories_searchable".into(), repositories_searchable.to_string()),
+        ("repository_type".into(), repository_type),
+        ("repository_target_id".into(), repository_target_id),
+        ("repository_template_id".into(), repository_template_id),
+        ("target_groups".into(), target_groups.join(", ")),
+        ("close_rule_sets".into(), close_rule_sets.join(", ")),
+        ("promote_rule_sets".into(), values_text(promote_rule_sets)),
+        ("finish_notify_creator".into(), finish_notify_creator.to_string()),
+        ("finish_notify_roles".into(), values_text(finish_notify_roles)),
+        ("promotion_notify_creator".into(), promotion_notify_creator.to_string()),
+        ("promotion_notify_roles".into(), values_text(promotion_notify_roles)),
+        ("drop_notify_creator".into(), drop_notify_creator.to_string()),
+        ("drop_notify_roles".into(), values_text(drop_notify_roles)),
+        ("resource_uri".into(), resource_uri),
+    ];
+    if let Some(properties) = properties {
+        fields.extend(properties.extra.into_iter().map(|(key, value)| (format!("properties.{key}"), value_text(value))));
+    }
+    fields.extend(extra.into_iter().map(|(key, value)| (key, value_text(value))));
+    let rows = fields.into_iter()
+        .map(|(key, value)| vec![Cell::from(key), Cell::from(value)])
+        .collect();
+    print_aligned(None, rows, &[false, true]);
+}
+
+fn values_text(values: Vec<serde_json::Value>) -> String {
+    values.into_iter().map(value_text).collect::<Vec<_>>().join(", ")
+}
+
+/// Text of a JSON value, without quotes around strings
+fn value_text(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s,
+        value => value.to_string(),
+    }
+}
+
 #[derive(Subcommand)]
 pub enum StagingCommands {
     /// Show available staging profiles
-    Profiles,
+    Profiles {
+        #[command(flatten)]
+        table: TableOptions,
+    },
     /// Show one staging profile
     Profile {
+        /// profile ID, or `@name=<profile name>`; optional if there is only one profile
         #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile: String,
+        profile: Option<ProfileSelector>,
     },
     /// Show all current staging repositories
     Repos {
+        /// `short` or `long` set of default columns; `json` is like `--output json`, `xml` shows the server's response
         #[arg(long, default_value = "short")]
         format: DirFormat,
+        #[command(flatten)]
+        table: TableOptions,
+        /// show only repositories matching any of these selectors
+        selectors: Vec<RepoSelector>,
     },
     /// Show one staging repository
     Repo {
-        repository_id: String,
+        repository_id: RepoSelector,
     },
     /// Retrieve current activity status on a staging repository
     #[command(name = "activity")]
     RepoActivity {
-        repository_id: String,
+        repository_id: RepoSelector,
         #[arg(long, default_value = "long")]
         format: DirFormat,
     },
     /// Create a new staging repository
     #[command(name = "start")]
     RepoStart {
-        // TODO: make profile_id optional, defaulting to single profile existing
-        // TODO: allow profile id syntax: `@name` to select profile by its name
+        /// profile ID, or `@name=<profile name>`; optional if there is only one profile, or with `--artifact`
         #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile_id: String,
+        profile_id: Option<ProfileSelector>,
+        /// coordinates `groupId[:artifactId[:version]]` of the released artifacts, to find matching profile on server
+        #[arg(short, long)]
+        artifact: Option<String>,
         #[arg(long, default_value = "short")]
         format: DirFormat,
         description: Option<String>,
     },
-    /// Finish (close) staging repository, exposing it to others for consuming.
-    /// Hidden until a working solution is found.
-    #[clap(hide(true))]
+    /// Upload a bundle, creating and closing a new staging repository from it
+    ///
+    /// Prints IDs of created staging repositories.
+    #[command(name = "bundle")]
+    Bundle {
+        /// Maven-layout directory of a single GAV to pack into bundle, or an existing bundle jar
+        local_path: PathBuf,
+    },
+    /// Set description of an open staging repository, finishing (closing) it
+    ///
+    /// Nexus changes the description only with a transition of the repository, so this is `finish` with a description.
     #[command(name = "desc")]
     RepoDescribe {
-        #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile_id: String,
-        repository_id: String,
-        description: Option<String>,
+        repository_id: RepoSelector,
+        description: String,
     },
-    /// Finish (close) staging repository, exposing it to others for consuming
+    /// Finish (close) staging repositories, exposing them to others for consuming
     #[command(name = "finish")]
     RepoFinish {
-        #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile_id: String,
-        repository_id: String,
+        /// validate this local directory (see `nexus validate`) before finishing, and refuse to finish on problems
+        #[arg(long)]
+        validate: Option<PathBuf>,
+        /// description of the repositories, recorded with the transition
+        #[arg(short, long)]
         description: Option<String>,
+        #[arg(required = true)]
+        repository_ids: Vec<RepoSelector>,
     },
-    /// Promote (release) staging repository into the target repository (typically `releases`)
+    /// Promote (release) staging repositories into their target repository (typically `releases`)
     #[command(name = "promote")]
     RepoPromote {
-        #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile_id: String,
-        repository_id: String,
+        /// description of the repositories, recorded with the transition
+        #[arg(short, long)]
+        description: Option<String>,
+        #[arg(required = true)]
+        repository_ids: Vec<RepoSelector>,
     },
-    /// Drop staging repository
+    /// Drop staging repositories
     #[command(name = "drop")]
     RepoDrop {
-        #[arg(short, long, env = "NEXUS_STAGING_PROFILE")]
-        profile_id: String,
-        // TODO: allow repository id syntax: `@desc=string` to select repo by description (must resolve to only one)
-        repository_ids: Vec<String>,
+        /// description of the repositories, recorded with the transition
+        #[arg(short, long)]
+        description: Option<String>,
+        #[arg(required = true)]
+        repository_ids: Vec<RepoSelector>,
     },
+    /// Drop stale staging repositories, left behind by failed builds
+    Gc {
+        /// drop repositories neither created nor updated for this long, like `7d` or `12h`
+        #[arg(long, value_parser = humantime::parse_duration)]
+        older_than: Duration,
+        /// drop only repositories in this state (`open`, `closed`, `released`)
+        #[arg(long, value_parser = parse_state)]
+        state: Option<RepositoryState>,
+        /// drop only repositories with description matching this glob pattern, like `CI build *`
+        #[arg(long)]
+        desc_pattern: Option<glob::Pattern>,
+        /// do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        #[command(flatten)]
+        table: TableOptions,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use nexus_client::model::{RepositoryState, StagingProfileRepository};
+
+    use super::{parse_state, select_stale};
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+    const CUTOFF: i64 = 1_700_000_000_000;
+
+    /// Repository with timestamps given in days relative to [CUTOFF]
+    fn repo(id: &str, state: &str, created: Option<i64>, updated: Option<i64>, description: &str) -> StagingProfileRepository {
+        StagingProfileRepository {
+            repository_id: id.to_string(),
+            repository_type: state.to_string(),
+            created_timestamp: created.map(|days| CUTOFF + days * DAY),
+            updated_timestamp: updated.map(|days| CUTOFF + days * DAY),
+            description: description.to_string(),
+            ..StagingProfileRepository::default()
+        }
+    }
+
+    fn repos() -> Vec<StagingProfileRepository> {
+        vec![
+            repo("orgexample-1001", "open", Some(-10), Some(-5), "CI build 1"),
+            repo("orgexample-1002", "open", Some(-10), Some(1), "CI build 2"),
+            repo("orgexample-1003", "closed", Some(-3), None, "CI build 3"),
+            repo("orgexample-1004", "closed", Some(-20), Some(-20), "Release 1.0"),
+            repo("orgexample-1005", "open", Some(1), None, "CI build 5"),
+            repo("orgexample-1006", "open", None, None, "CI build 6"),
+        ]
+    }
+
+    fn ids(list: Vec<StagingProfileRepository>) -> Vec<String> {
+        list.into_iter().map(|repo| repo.repository_id).collect()
+    }
+
+    #[test]
+    fn test_select_stale_by_cutoff() {
+        let cutoff = DateTime::<Utc>::from_timestamp_millis(CUTOFF).unwrap();
+        assert_eq!(ids(select_stale(repos(), cutoff, None, None)), vec!["orgexample-1001", "orgexample-1003", "orgexample-1004"]);
+    }
+
+    #[test]
+    fn test_select_stale_by_state() -> anyhow::Result<()> {
+        let cutoff = DateTime::<Utc>::from_timestamp_millis(CUTOFF).unwrap();
+        let closed = select_stale(repos(), cutoff, Some(&RepositoryState::Closed), None);
+        assert_eq!(ids(closed), vec!["orgexample-1003", "orgexample-1004"]);
+        let ci_builds = select_stale(repos(), cutoff, Some(&RepositoryState::Closed), Some(&glob::Pattern::new("CI build *")?));
+        assert_eq!(ids(ci_builds), vec!["orgexample-1003"]);
+        assert_eq!(ids(select_stale(repos(), cutoff, Some(&RepositoryState::Released), None)), Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_state() -> anyhow::Result<()> {
+        assert_eq!(parse_state("open")?, RepositoryState::Open);
+        assert_eq!(parse_state("CLOSED")?, RepositoryState::Closed);
+        assert!(parse_state("transitioning").is_err());
+        Ok(())
+    }
 }
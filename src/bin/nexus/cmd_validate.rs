@@ -0,0 +1,29 @@
+use std::path::Path;
+
+use nexus_client::validate::validate_bundle;
+
+use crate::output::{OutputFormat, print_list};
+
+/// Prints problems found in the local bundle, and fails if there are any.
+pub fn cmd_validate(dir: &Path, output: Option<OutputFormat>) -> anyhow::Result<()> {
+    if !dir.is_dir() {
+        anyhow::bail!("Directory expected: {}", dir.display());
+    }
+    let problems = validate_bundle(dir)?;
+    if let Some(output) = output {
+        print_list(output, &problems)?;
+    }
+    if problems.is_empty() {
+        log::info!("No problems found in {}", dir.display());
+        return Ok(());
+    }
+    let mut last_gav = None;
+    for problem in problems.iter().filter(|_| output.is_none()) {
+        if last_gav != Some(&problem.gav) {
+            println!("{}", problem.gav);
+            last_gav = Some(&problem.gav);
+        }
+        println!("  [{}] {}", problem.category, problem.message);
+    }
+    anyhow::bail!("{} problems found in {}", problems.len(), dir.display())
+}
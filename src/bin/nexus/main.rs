@@ -1,61 +1,123 @@
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use clap::{Parser, Subcommand, ValueEnum};
 
+use cmd_cache::CacheCommands;
 use cmd_staging::StagingCommands;
-use nexus_client::{http_upload, NexusClient, NexusRepository};
-use nexus_client::remote_sync::http_download_tree;
+use nexus_client::{ByteRange, http_upload, NexusClient, NexusRepository};
+use nexus_client::cache::Cache;
+use nexus_client::mirror::MirrorOptions;
+use nexus_client::remote_sync::{http_download_glob, http_download_tree, is_glob, TransferredFile, upload_signature, UploadOptions};
+use nexus_client::signing::{GpgSigner, is_signable, Signer};
 
+use crate::cmd_du::DuGrouping;
+use crate::cmd_find::{FindAction, FindFilters};
 use crate::cmd_list::DirPrinter;
+use crate::cmd_remove::RemoveOptions;
 use crate::nexus_uri::NexusRemoteUri;
+use crate::output::{FileRecord, OutputFormat, print_list};
+use crate::table::TableOptions;
 
+mod cmd_cache;
 mod cmd_staging;
 mod nexus_uri;
 mod cmd_list;
+mod cmd_cat;
+mod cmd_copy;
+mod cmd_find;
+mod cmd_du;
+mod cmd_diff;
+mod cmd_mirror;
+mod cmd_remove;
+mod cmd_validate;
+mod selector;
+mod output;
+mod table;
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../test_server.rs"]
+mod test_server;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    if let Err(_) = std::env::var("RUST_LOG") {
+    if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
     }
     env_logger::init();
     let cli = Cli::parse();
+    let output = cli.output;
     match cli.command {
         Commands::Staging { staging_command } => {
-            cmd_staging::cmd_staging(staging_command).await?;
+            cmd_staging::cmd_staging(staging_command, output).await?;
         }
-        Commands::Download { local_path, nexus_uri, } => {
+        Commands::Cache { cache_command } => {
+            cmd_cache::cmd_cache(cache_command, output)?;
+        }
+        Commands::Download { flatten, parents, local_path, nexus_uri, } => {
             log::info!("downloading {local_path:?} from {nexus_uri}");
             let nexus = nexus_public_client()?;
             match (local_path.is_dir(), nexus_uri.is_dir()) {
+                _ if is_glob(&nexus_uri.repo_path) => {
+                    // pattern download
+                    let downloaded = http_download_glob(&nexus, &nexus_uri.repo_id, &nexus_uri.repo_path, &local_path, flatten).await?;
+                    if let Some(output) = output {
+                        print_list(output, &file_records(&nexus_uri.repo_id, downloaded))?;
+                    }
+                }
                 (_, true) => {
                     // tree download
-                    http_download_tree(&nexus, &nexus_uri.repo_id, &nexus_uri.repo_path, &local_path).await?;
+                    let downloaded = http_download_tree(&nexus, &nexus_uri.repo_id, &nexus_uri.repo_path, &local_path).await?;
+                    if let Some(output) = output {
+                        print_list(output, &file_records(&nexus_uri.repo_id, downloaded))?;
+                    }
                 }
                 (local_is_dir, false) => {
                     // single file download
                     let local_path = if local_is_dir {
                         let file_name = match nexus_uri.repo_path.rfind("/") {
-                            None => panic!("There must always be at least one slash: {nexus_uri}"),
+                            None => anyhow::bail!("There must always be at least one slash: {nexus_uri}"),
                             Some(index) => &nexus_uri.repo_path[index + 1..]
                         };
                         local_path.join(file_name)
                     } else {
                         local_path
                     };
-                    let url = nexus.download_file(&nexus_uri.repo_id, &local_path, &nexus_uri.repo_path).await?;
+                    let url = nexus.download_file(&nexus_uri.repo_id, &local_path, &nexus_uri.repo_path, parents).await?;
                     log::info!("File {} downloaded from {url}", local_path.display());
+                    if let Some(output) = output {
+                        print_list(output, &[FileRecord {
+                            local_path: local_path.display().to_string(),
+                            remote_uri: nexus_uri.to_string(),
+                        }])?;
+                    }
                 }
             }
         }
-        Commands::Upload { local_path, nexus_uri } => {
+        Commands::Upload { sign, gpg_key, include, exclude, no_default_ignores, no_ignore_files, follow_symlinks, local_path, nexus_uri } => {
             log::info!("uploading {local_path:?} to {nexus_uri}");
             let nexus = nexus_client()?;
+            let signer = sign.then(|| Arc::new(GpgSigner {
+                key_id: gpg_key,
+                ..GpgSigner::from_env()
+            }) as Arc<dyn Signer>);
+            let signer = signer.as_ref();
             // dir-dir checking TODO perhaps move this into upload function?
             match (local_path.is_dir(), nexus_uri.is_dir()) {
                 (true, true) => {
                     // tree upload
-                    http_upload(&nexus, &nexus_uri.repo_id, &nexus_uri.repo_path, &local_path).await?;
+                    let options = UploadOptions {
+                        include,
+                        exclude,
+                        default_ignores: !no_default_ignores,
+                        ignore_files: !no_ignore_files,
+                        follow_symlinks,
+                    };
+                    let uploaded = http_upload(&nexus, &nexus_uri.repo_id, &nexus_uri.repo_path, &local_path, signer, &options).await?;
+                    if let Some(output) = output {
+                        print_list(output, &file_records(&nexus_uri.repo_id, uploaded))?;
+                    }
                 }
                 (false, remote_is_dir) => {
                     // single file upload
@@ -68,24 +130,73 @@ async fn main() -> anyhow::Result<()> {
                     };
                     let url = nexus.upload_file(&nexus_uri.repo_id, &local_path, &remote_path).await?;
                     log::info!("File {} uploaded to {url}", local_path.display());
+                    if let (Some(signer), true) = (signer, is_signable(&local_path)) {
+                        upload_signature(&nexus, &nexus_uri.repo_id, signer, &local_path, &remote_path).await?;
+                    }
+                    if let Some(output) = output {
+                        print_list(output, &[FileRecord {
+                            local_path: local_path.display().to_string(),
+                            remote_uri: format!("::/{}{remote_path}", nexus_uri.repo_id),
+                        }])?;
+                    }
                 }
                 (local_is_dir, remote_is_dir) => anyhow::bail!("Unsupported transfer: localdir({local_is_dir}) -> remotedir({remote_is_dir})")
             }
         }
-        Commands::Remove { nexus_uri } => {
-            //TODO if not --force, require the repository to be open and non-transitioning
-            //TODO support wildcards?
-            //TODO if remote specified is not dir, make sure it really is not dir on nexus, otherwise fail (caller must prove to be aware that he deletes a directory!)
+        Commands::Remove { recursive, force, yes, dry_run, jobs, nexus_uris } => {
             let nexus = crate::nexus_client()?;
-            let request = NexusRepository::nexus_readwrite(&nexus_uri.repo_id)
-                .delete(&nexus_uri.repo_path);
-            let response = nexus.execute(request).await?;
-            response.check().await?;
-            log::warn!("Removed: {nexus_uri}");
+            let options = RemoveOptions { recursive, force, yes, dry_run, jobs };
+            cmd_remove::cmd_remove(&nexus, &nexus_uris, options, output).await?;
+        }
+        Commands::Find { format, long, filters, delete, force, yes, download, nexus_uri } => {
+            let action = match (delete, download) {
+                (true, Some(_)) => anyhow::bail!("Options --delete and --download cannot be combined"),
+                (true, None) => FindAction::Delete(RemoveOptions { recursive: true, force, yes, dry_run: false, jobs: 1 }),
+                (false, Some(local_root)) => FindAction::Download { local_root },
+                (false, None) => FindAction::Print,
+            };
+            let nexus = if delete { nexus_client()? } else { nexus_public_client()? };
+            let format = if long { DirFormat::Long } else { format };
+            cmd_find::cmd_find(nexus, &nexus_uri, &filters, format, action, output).await?;
+        }
+        Commands::DiskUsage { by, depth, table, nexus_uri } => {
+            let nexus = nexus_public_client()?;
+            cmd_du::cmd_du(&nexus, &nexus_uri, by, depth, &table, output).await?;
+        }
+        Commands::Diff { local_path, nexus_uri } => {
+            let nexus = nexus_public_client()?;
+            cmd_diff::cmd_diff(&nexus, &local_path, &nexus_uri, output).await?;
+        }
+        Commands::Mirror { delete, include, exclude, dry_run, source, target } => {
+            let options = MirrorOptions { delete, include, exclude, dry_run };
+            cmd_mirror::cmd_mirror(&source, &target, &options, output).await?;
+        }
+        Commands::Copy { source, target } => {
+            let nexus = nexus_client()?;
+            cmd_copy::cmd_copy(&nexus, &source, &target, false, output).await?;
+        }
+        Commands::Move { source, target } => {
+            let nexus = nexus_client()?;
+            cmd_copy::cmd_copy(&nexus, &source, &target, true, output).await?;
+        }
+        Commands::Cat { range, nexus_uris } => {
+            let nexus = nexus_public_client()?;
+            cmd_cat::cmd_cat(&nexus, &nexus_uris, range).await?;
+        }
+        Commands::Head { lines, bytes, nexus_uri } => {
+            let nexus = nexus_public_client()?;
+            cmd_cat::cmd_head(&nexus, &nexus_uri, lines, bytes).await?;
+        }
+        Commands::Stat { nexus_uris } => {
+            let nexus = nexus_public_client()?;
+            cmd_cat::cmd_stat(&nexus, &nexus_uris, output).await?;
+        }
+        Commands::Validate { local_path } => {
+            cmd_validate::cmd_validate(&local_path, output)?;
         }
         Commands::List { recurse, format, long, nexus_uri } => {
             let nexus = crate::nexus_public_client()?;
-            if format == DirFormat::Json {
+            if format == DirFormat::Json && output.is_none() {
                 let request = NexusRepository::nexus_readonly(&nexus_uri.repo_id)
                     .list(&nexus_uri.repo_path);
                 let response = nexus.execute(request).await?;
@@ -93,9 +204,7 @@ async fn main() -> anyhow::Result<()> {
                 println!("{json}");
                 return Ok(());
             }
-            let dir_printer = DirPrinter {
-                format: if long { DirFormat::Long } else { format },
-            };
+            let dir_printer = DirPrinter::new(if long { DirFormat::Long } else { format }, output)?;
             cmd_list::cmd_list(nexus, &nexus_uri, dir_printer, recurse).await?;
         }
     }
@@ -111,34 +220,99 @@ pub enum DirFormat {
     Xml,
 }
 
+fn file_records(repo_id: &str, files: Vec<TransferredFile>) -> Vec<FileRecord> {
+    files.into_iter()
+        .map(|file| FileRecord {
+            local_path: file.local_path.display().to_string(),
+            remote_uri: format!("::/{repo_id}{}", file.remote_path),
+        })
+        .collect()
+}
+
 fn nexus_client() -> anyhow::Result<NexusClient> {
     let nexus_url = nexus_client::nexus_url()?;
     let (user, password) = nexus_client::get_credentials(&nexus_url)?;
-    Ok(NexusClient::login(nexus_url, &user, &password)?)
+    with_cache(NexusClient::login(nexus_url, &user, &password)?)
+}
+
+/// Asks user on terminal; anything but `y` or `yes` means no.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// The user we are authenticated as
+fn current_user() -> anyhow::Result<String> {
+    let nexus_url = nexus_client::nexus_url()?;
+    let (user, _) = nexus_client::get_credentials(&nexus_url)?;
+    Ok(user)
 }
 
 fn nexus_public_client() -> anyhow::Result<NexusClient> {
-    Ok(NexusClient::anonymous(nexus_client::nexus_url()?)?)
+    with_cache(NexusClient::anonymous(nexus_client::nexus_url()?)?)
+}
+
+/// Downloads go through the local cache, when configured by `NEXUS_CACHE_DIR`
+fn with_cache(nexus: NexusClient) -> anyhow::Result<NexusClient> {
+    Ok(match Cache::from_env()? {
+        None => nexus,
+        Some(cache) => nexus.with_cache(cache),
+    })
 }
 
 /// Sonatype Nexus Unofficial Client
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, bin_name = "nexus")]
 struct Cli {
+    /// print the result in structured format, see `doc/output.md`
+    #[arg(short, long, global = true)]
+    output: Option<OutputFormat>,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Download repository - entire or a subtree
+    /// Download repository - entire or a subtree, or files matching a pattern
     Download {
+        /// put all downloaded files directly into the local directory, without their remote layout
+        #[arg(long)]
+        flatten: bool,
+        /// create missing parent directories of the downloaded file
+        #[arg(short, long)]
+        parents: bool,
         local_path: PathBuf,
+        /// file or directory; path segments may contain `*`, `?` and `[...]`, like `::/releases/org/foo/*/1.*/*.pom`
         #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
         nexus_uri: NexusRemoteUri,
     },
     /// Upload local dir to a repository
     Upload {
+        /// upload detached gpg signature (`*.asc`) along with each artifact
+        #[arg(long)]
+        sign: bool,
+        /// key to sign with; gpg's default key when missing
+        #[arg(long, env = "NEXUS_GPG_KEY")]
+        gpg_key: Option<String>,
+        /// upload only files matching this glob pattern (relative to uploaded directory); can be repeated
+        #[arg(long)]
+        include: Vec<glob::Pattern>,
+        /// do not upload files or directories matching this glob pattern; can be repeated
+        #[arg(long)]
+        exclude: Vec<glob::Pattern>,
+        /// upload also `.DS_Store`, `.git`, `_remote.repositories` and `*.lastUpdated`
+        #[arg(long)]
+        no_default_ignores: bool,
+        /// do not read `.nexusignore` files
+        #[arg(long)]
+        no_ignore_files: bool,
+        /// upload targets of symbolic links, instead of skipping them
+        #[arg(long)]
+        follow_symlinks: bool,
         local_path: PathBuf,
         #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
         nexus_uri: NexusRemoteUri,
@@ -158,16 +332,143 @@ enum Commands {
         nexus_uri: NexusRemoteUri,
     },
 
-    /// Remove a path on remote repo (file of directory with its contents)
+    /// Remove paths on remote repo (files or directories with their contents); wildcards are expanded remotely
     #[clap(name = "rm")]
     Remove {
+        /// remove a directory even when given without trailing slash
+        #[arg(short, long)]
+        recursive: bool,
+        /// remove from staging repository even when it is not open, or is transitioning
+        #[arg(short, long)]
+        force: bool,
+        /// do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// only show what would be removed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        /// how many paths are removed concurrently
+        #[arg(short, long, default_value = "4")]
+        jobs: usize,
+        /// paths to remove; segments may contain `*`, `?` and `[...]`, like `::/snapshots/org/foo/*/1.0-SNAPSHOT/`
+        #[arg(required = true, value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uris: Vec<NexusRemoteUri>,
+    },
+    /// Find files and directories in a remote tree
+    Find {
+        #[arg(long, default_value = "short")]
+        format: DirFormat,
+        /// shortcut for `--format=long`
+        #[arg(short, long)]
+        long: bool,
+        #[command(flatten)]
+        filters: FindFilters,
+        /// remove found paths, after confirmation
+        #[arg(long)]
+        delete: bool,
+        /// with `--delete`, remove from staging repository even when it is not open, or is transitioning
+        #[arg(short, long)]
+        force: bool,
+        /// do not ask for confirmation of `--delete`
+        #[arg(short, long)]
+        yes: bool,
+        /// download found files into this directory, keeping their layout
+        #[arg(long)]
+        download: Option<PathBuf>,
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uri: NexusRemoteUri,
+    },
+    /// Summarize sizes of files in a remote tree, per subdirectory or per Maven coordinates
+    #[clap(name = "du")]
+    DiskUsage {
+        #[arg(long, value_enum, default_value = "dir")]
+        by: DuGrouping,
+        /// with `--by dir`, how many levels of subdirectories to summarize; 0 gives just the total
+        #[arg(short, long, default_value_t = 1)]
+        depth: usize,
+        #[command(flatten)]
+        table: TableOptions,
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uri: NexusRemoteUri,
+    },
+    /// Compare local directory with remote one, showing files missing on either side or differing
+    Diff {
+        local_path: PathBuf,
         #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
         nexus_uri: NexusRemoteUri,
     },
+    /// Make target directory a copy of source one; one of them is remote, the other local
+    Mirror {
+        /// remove files of target which are missing in source
+        #[arg(long)]
+        delete: bool,
+        /// mirror only files matching this glob pattern (relative to mirrored directories); can be repeated
+        #[arg(long)]
+        include: Vec<glob::Pattern>,
+        /// do not mirror (nor delete) files matching this glob pattern; can be repeated
+        #[arg(long)]
+        exclude: Vec<glob::Pattern>,
+        /// only show what would be done
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        /// remote URI like `::/releases/org/example/`, or local directory
+        source: String,
+        /// local directory, or remote URI
+        target: String,
+    },
+    /// Copy a remote file or directory to another path or repository, without downloading it locally
+    #[clap(name = "cp")]
+    Copy {
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        source: NexusRemoteUri,
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        target: NexusRemoteUri,
+    },
+    /// Move a remote file or directory; the source is removed only after all copies are verified
+    #[clap(name = "mv")]
+    Move {
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        source: NexusRemoteUri,
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        target: NexusRemoteUri,
+    },
+    /// Print content of remote files to stdout
+    Cat {
+        /// print only this part of content, as `<start>-[<end>]` byte offsets (end is inclusive)
+        #[arg(long)]
+        range: Option<ByteRange>,
+        #[arg(required = true, value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uris: Vec<NexusRemoteUri>,
+    },
+    /// Print beginning of a remote file
+    Head {
+        /// number of lines to print
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: usize,
+        /// print this many bytes instead of lines
+        #[arg(short = 'c', long)]
+        bytes: Option<u64>,
+        #[arg(value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uri: NexusRemoteUri,
+    },
+    /// Show size, last modification, checksums and content type of remote files
+    Stat {
+        #[arg(required = true, value_parser = clap::value_parser ! (NexusRemoteUri))]
+        nexus_uris: Vec<NexusRemoteUri>,
+    },
+    /// Check local Maven-layout directory against Maven Central requirements
+    Validate {
+        local_path: PathBuf,
+    },
     /// Manage staging repositories.
     /// Only for Nexus instances with "staging plugin" configured.
     Staging {
         #[command(subcommand)]
         staging_command: StagingCommands,
     },
+    /// Local cache of downloaded files
+    Cache {
+        #[command(subcommand)]
+        cache_command: CacheCommands,
+    },
 }
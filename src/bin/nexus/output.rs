@@ -0,0 +1,325 @@
+//! Structured output of commands, selected by the global `--output` option.
+//!
+//! The schema of each record is documented in `doc/output.md`; keep it in sync.
+use clap::ValueEnum;
+use serde::Serialize;
+
+use nexus_client::model::{DirEntry, StagingActivity, StagingProfile, StagingProfileRepository};
+use nexus_client::validate::ValidationProblem;
+
+use crate::table::{Cell, print_aligned};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON array of records; a single object for commands showing one thing
+    Json,
+    /// YAML, with the same structure as JSON
+    Yaml,
+    /// aligned columns with a header line
+    Table,
+    /// tab-separated columns, without header
+    Tsv,
+}
+
+/// Record that can be printed as a row of table
+pub trait Tabular {
+    fn columns() -> &'static [&'static str];
+    fn row(&self) -> Vec<String>;
+}
+
+pub fn print_list<T: Serialize + Tabular>(format: OutputFormat, items: &[T]) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        OutputFormat::Table => print_table(T::columns(), items.iter().map(Tabular::row).collect()),
+        OutputFormat::Tsv => {
+            for item in items {
+                let row: Vec<String> = item.row().iter()
+                    .map(|value| value.replace(['\t', '\n'], " "))
+                    .collect();
+                println!("{}", row.join("\t"));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn print_item<T: Serialize + Tabular>(format: OutputFormat, item: &T) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        OutputFormat::Table | OutputFormat::Tsv => print_list(format, std::slice::from_ref(item))?,
+    }
+    Ok(())
+}
+
+fn print_table(columns: &[&str], rows: Vec<Vec<String>>) {
+    let header = columns.iter().map(|column| Cell::from(column.to_uppercase())).collect();
+    let rows = rows.into_iter()
+        .map(|row| row.into_iter().map(Cell::from).collect())
+        .collect();
+    print_aligned(Some(header), rows, &[]);
+}
+
+/// A file transferred by `upload` or `download`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRecord {
+    pub local_path: String,
+    /// like `::/releases/org/example/demo/1.0/demo-1.0.pom`
+    pub remote_uri: String,
+}
+
+impl Tabular for FileRecord {
+    fn columns() -> &'static [&'static str] {
+        &["local", "remote"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.local_path.clone(), self.remote_uri.clone()]
+    }
+}
+
+/// A file copied by `cp` or `mv`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyRecord {
+    pub source_uri: String,
+    pub target_uri: String,
+    pub size: Option<u64>,
+}
+
+impl Tabular for CopyRecord {
+    fn columns() -> &'static [&'static str] {
+        &["source", "target", "size"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.source_uri.clone(), self.target_uri.clone(), self.size.map(|size| size.to_string()).unwrap_or_default()]
+    }
+}
+
+/// Disk usage of a directory or Maven coordinates, by `du`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuRecord {
+    /// remote directory URI, or coordinates like `org.example:demo:1.0`
+    pub path: String,
+    pub files: u64,
+    /// in bytes
+    pub size: u64,
+}
+
+impl Tabular for DuRecord {
+    fn columns() -> &'static [&'static str] {
+        &["size", "files", "path"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.size.to_string(), self.files.to_string(), self.path.clone()]
+    }
+}
+
+/// A file that differs between local and remote tree, by `diff`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffRecord {
+    /// relative to both compared directories
+    pub path: String,
+    /// `only-local`, `only-remote` or `differs`
+    pub status: &'static str,
+    pub local_size: Option<u64>,
+    pub remote_size: Option<u64>,
+    /// `size` or `sha1`, for differing files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Tabular for DiffRecord {
+    fn columns() -> &'static [&'static str] {
+        &["status", "path", "local_size", "remote_size", "detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let size = |size: Option<u64>| size.map(|size| size.to_string()).unwrap_or_default();
+        vec![self.status.to_string(), self.path.clone(), size(self.local_size), size(self.remote_size), self.detail.clone().unwrap_or_default()]
+    }
+}
+
+/// A change made (or planned, with `--dry-run`) by `mirror`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorRecord {
+    /// `download`, `upload`, `delete-local` or `delete-remote`
+    pub action: &'static str,
+    /// relative to both mirrored directories
+    pub path: String,
+}
+
+impl Tabular for MirrorRecord {
+    fn columns() -> &'static [&'static str] {
+        &["action", "path"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.action.to_string(), self.path.clone()]
+    }
+}
+
+/// A path removed by `rm`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedRecord {
+    pub remote_uri: String,
+    /// why the path could not be removed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Tabular for RemovedRecord {
+    fn columns() -> &'static [&'static str] {
+        &["removed", "error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.remote_uri.clone(), self.error.clone().unwrap_or_default()]
+    }
+}
+
+/// Metadata of a remote file, shown by `stat`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatRecord {
+    pub remote_uri: String,
+    pub size: Option<u64>,
+    /// RFC 3339 timestamp
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+    pub sha1: Option<String>,
+    pub md5: Option<String>,
+    /// only when the storage item info was not available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl Tabular for StatRecord {
+    fn columns() -> &'static [&'static str] {
+        &["file", "size", "last_modified", "content_type", "sha1"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.remote_uri.clone(),
+            self.size.map(|size| size.to_string()).unwrap_or_default(),
+            self.last_modified.clone().unwrap_or_default(),
+            self.content_type.clone().unwrap_or_default(),
+            self.sha1.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// An operation performed on staging repository - `start`, `bundle`, `desc`, `finish`, `promote`, `drop`, `gc`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagingActionRecord {
+    pub action: &'static str,
+    pub repository_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Tabular for StagingActionRecord {
+    fn columns() -> &'static [&'static str] {
+        &["action", "repository", "description"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.action.to_string(), self.repository_id.clone(), self.description.clone().unwrap_or_default()]
+    }
+}
+
+/// Content of the local cache, shown by `cache stats`; `cache clean` shows what was removed
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheRecord {
+    pub cache_dir: String,
+    pub entries: usize,
+    pub objects: usize,
+    pub size: u64,
+    pub max_size: u64,
+}
+
+impl Tabular for CacheRecord {
+    fn columns() -> &'static [&'static str] {
+        &["cache_dir", "entries", "objects", "size", "max_size"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.cache_dir.clone(),
+            self.entries.to_string(),
+            self.objects.to_string(),
+            self.size.to_string(),
+            self.max_size.to_string(),
+        ]
+    }
+}
+
+impl Tabular for StagingProfile {
+    fn columns() -> &'static [&'static str] {
+        &["id", "name", "mode", "target"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.id.clone(), self.name.clone(), self.mode.clone(), self.promotion_target_repository.clone()]
+    }
+}
+
+impl Tabular for StagingProfileRepository {
+    fn columns() -> &'static [&'static str] {
+        &["id", "state", "transitioning", "profile", "updated", "description"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.repository_id.clone(),
+            self.repository_type.clone(),
+            self.transitioning.map(|transitioning| transitioning.to_string()).unwrap_or_default(),
+            self.profile_name.clone(),
+            self.updated.clone(),
+            self.description.clone(),
+        ]
+    }
+}
+
+impl Tabular for StagingActivity {
+    fn columns() -> &'static [&'static str] {
+        &["activity", "started", "stopped", "last_event"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let last_event = self.events.last().map_or("", |event| &event.name);
+        vec![self.name.clone(), self.started.clone(), self.stopped.clone(), last_event.to_string()]
+    }
+}
+
+impl Tabular for DirEntry {
+    fn columns() -> &'static [&'static str] {
+        &["last_modified", "size", "path"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let size = if self.leaf { self.size_on_disk.to_string() } else { "/".to_string() };
+        vec![self.last_modified.clone(), size, self.relative_path.clone()]
+    }
+}
+
+impl Tabular for ValidationProblem {
+    fn columns() -> &'static [&'static str] {
+        &["gav", "category", "message"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.gav.to_string(), self.category.to_string(), self.message.clone()]
+    }
+}
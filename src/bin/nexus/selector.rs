@@ -0,0 +1,308 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use nexus_client::{NexusClient, StagingProfiles, StagingRepositories};
+use nexus_client::model::{RepositoryState, StagingProfileRepository};
+
+const SELECTOR_START: char = '@';
+
+/// Selects staging repositories.
+///
+/// Syntax: either a plain repository ID, or comma-separated filters, each starting with `@`:
+/// - `@name=<profile name>` - repositories of given staging profile
+/// - `@desc=<description>` - repositories with exactly this description
+/// - `@state=<state>` - repositories in given state (`open`, `closed`, `released`)
+/// - `@mine` - repositories created by current user
+/// - `@latest` - only the most recently created one of those matching other filters
+#[derive(Clone, Debug)]
+pub enum RepoSelector {
+    Id(String),
+    Filters(Vec<RepoFilter>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RepoFilter {
+    ProfileName(String),
+    Description(String),
+    State(RepositoryState),
+    Mine,
+    Latest,
+}
+
+impl RepoSelector {
+    /// Picks matching repositories from the list.
+    pub fn select<'a>(&self, repos: &'a [StagingProfileRepository]) -> anyhow::Result<Vec<&'a StagingProfileRepository>> {
+        let filters = match self {
+            RepoSelector::Id(id) => return Ok(repos.iter().filter(|repo| &repo.repository_id == id).collect()),
+            RepoSelector::Filters(filters) => filters,
+        };
+        let user = if filters.contains(&RepoFilter::Mine) {
+            Some(crate::current_user()?)
+        } else {
+            None
+        };
+        let mut selected: Vec<&StagingProfileRepository> = repos.iter()
+            .filter(|repo| filters.iter().all(|filter| match filter {
+                RepoFilter::ProfileName(name) => &repo.profile_name == name,
+                RepoFilter::Description(description) => &repo.description == description,
+                RepoFilter::State(state) => repo.state().as_ref() == Some(state),
+                RepoFilter::Mine => Some(&repo.user_id) == user.as_ref(),
+                RepoFilter::Latest => true,
+            }))
+            .collect();
+        if filters.contains(&RepoFilter::Latest) {
+            selected = selected.into_iter()
+                .max_by_key(|repo| repo.created_timestamp)
+                .into_iter()
+                .collect();
+        }
+        Ok(selected)
+    }
+}
+
+impl FromStr for RepoSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(s) = s.strip_prefix(SELECTOR_START) else {
+            return Ok(Self::Id(s.to_string()));
+        };
+        let mut filters = Vec::new();
+        // the separator includes the `@`, so that descriptions may contain commas
+        for filter in s.split(",@") {
+            let filter = match filter.split_once('=') {
+                Some(("name", name)) => RepoFilter::ProfileName(name.to_string()),
+                Some(("desc", description)) => RepoFilter::Description(description.to_string()),
+                Some(("state", state)) => RepoFilter::State(RepositoryState::from(state.to_string())),
+                None if filter == "mine" => RepoFilter::Mine,
+                None if filter == "latest" => RepoFilter::Latest,
+                _ => anyhow::bail!("Unknown repository selector: '@{filter}'"),
+            };
+            filters.push(filter);
+        }
+        Ok(Self::Filters(filters))
+    }
+}
+
+impl Display for RepoSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoSelector::Id(id) => f.write_str(id),
+            RepoSelector::Filters(filters) => {
+                let filters: Vec<String> = filters.iter()
+                    .map(|filter| match filter {
+                        RepoFilter::ProfileName(name) => format!("@name={name}"),
+                        RepoFilter::Description(description) => format!("@desc={description}"),
+                        RepoFilter::State(state) => format!("@state={state}"),
+                        RepoFilter::Mine => "@mine".to_string(),
+                        RepoFilter::Latest => "@latest".to_string(),
+                    })
+                    .collect();
+                f.write_str(&filters.join(","))
+            }
+        }
+    }
+}
+
+/// Resolves each selector to exactly one staging repository ID.
+/// Plain IDs are passed through without asking the server.
+pub async fn resolve_repository_ids(nexus: &NexusClient, selectors: &[RepoSelector]) -> anyhow::Result<Vec<String>> {
+    let mut repos = None;
+    let mut ids = Vec::with_capacity(selectors.len());
+    for selector in selectors {
+        if let RepoSelector::Id(id) = selector {
+            ids.push(id.clone());
+            continue;
+        }
+        if repos.is_none() {
+            repos = Some(nexus.execute(StagingRepositories::list()).await?.parsed().await?);
+        }
+        let selected = selector.select(repos.as_ref().unwrap())?;
+        match selected.as_slice() {
+            [] => anyhow::bail!("No staging repository matches '{selector}'"),
+            [repo] => {
+                log::debug!("'{selector}' resolved to {}", repo.repository_id);
+                ids.push(repo.repository_id.clone())
+            }
+            _ => {
+                let matching: Vec<&str> = selected.iter().map(|repo| repo.repository_id.as_str()).collect();
+                anyhow::bail!("Selector '{selector}' must match exactly one staging repository, but it matches: {}", matching.join(" "))
+            }
+        }
+    }
+    Ok(ids)
+}
+
+pub async fn resolve_repository_id(nexus: &NexusClient, selector: &RepoSelector) -> anyhow::Result<String> {
+    let mut ids = resolve_repository_ids(nexus, std::slice::from_ref(selector)).await?;
+    Ok(ids.remove(0))
+}
+
+/// Resolves the profile to use when it might not be specified:
+/// - by matching the `artifact` coordinates (`groupId[:artifactId[:version]]`) on the server
+/// - or, the only profile available to the user
+pub async fn resolve_profile_id(nexus: &NexusClient, profile: Option<&ProfileSelector>, artifact: Option<&str>) -> anyhow::Result<String> {
+    if let Some(profile) = profile {
+        if let Some(artifact) = artifact {
+            log::warn!("Profile '{profile}' is specified, not evaluating artifact '{artifact}'");
+        }
+        return profile.resolve(nexus).await;
+    }
+    let profiles = match artifact {
+        Some(artifact) => {
+            let mut coordinates = artifact.splitn(3, ':');
+            let group_id = coordinates.next().unwrap_or("");
+            let artifact_id = coordinates.next().unwrap_or("");
+            let version = coordinates.next().unwrap_or("");
+            let profiles = nexus.execute(StagingProfiles::evaluate(group_id, artifact_id, version)).await?.parsed().await?;
+            if profiles.is_empty() {
+                anyhow::bail!("No staging profile matches artifact '{artifact}'");
+            }
+            // profiles come ordered by their priority, so the first one wins
+            profiles
+        }
+        None => {
+            let profiles = nexus.execute(StagingProfiles::list()).await?.parsed().await?;
+            if profiles.len() != 1 {
+                let names: Vec<&str> = profiles.iter().map(|profile| profile.name.as_str()).collect();
+                anyhow::bail!("Staging profile must be specified, available profiles are: {}", names.join(", "));
+            }
+            profiles
+        }
+    };
+    let profile = &profiles[0];
+    log::info!("Using staging profile {} ({})", profile.id, profile.name);
+    Ok(profile.id.clone())
+}
+
+/// Selects a staging profile, either by its ID, or by name with `@name=<profile name>`.
+#[derive(Clone, Debug)]
+pub enum ProfileSelector {
+    Id(String),
+    Name(String),
+}
+
+impl ProfileSelector {
+    pub async fn resolve(&self, nexus: &NexusClient) -> anyhow::Result<String> {
+        match self {
+            ProfileSelector::Id(id) => Ok(id.clone()),
+            ProfileSelector::Name(name) => {
+                let profiles = nexus.execute(StagingProfiles::list()).await?.parsed().await?;
+                match profiles.into_iter().find(|profile| &profile.name == name) {
+                    None => anyhow::bail!("No staging profile named '{name}'"),
+                    Some(profile) => {
+                        log::debug!("'{self}' resolved to {}", profile.id);
+                        Ok(profile.id)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for ProfileSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("@name=") {
+            Some(name) => Ok(Self::Name(name.to_string())),
+            None if s.starts_with(SELECTOR_START) => anyhow::bail!("Unknown profile selector: '{s}'"),
+            None => Ok(Self::Id(s.to_string())),
+        }
+    }
+}
+
+impl Display for ProfileSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileSelector::Id(id) => f.write_str(id),
+            ProfileSelector::Name(name) => write!(f, "@name={name}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nexus_client::model::{RepositoryState, StagingProfileRepository};
+
+    use super::{ProfileSelector, RepoFilter, RepoSelector};
+
+    fn repo(id: &str, profile_name: &str, state: &str, description: &str, created: i64) -> StagingProfileRepository {
+        serde_json::from_value(serde_json::json!({
+            "repositoryId": id,
+            "profileName": profile_name,
+            "type": state,
+            "description": description,
+            "createdTimestamp": created,
+        })).unwrap()
+    }
+
+    fn ids(selected: Vec<&StagingProfileRepository>) -> Vec<&str> {
+        selected.iter().map(|repo| repo.repository_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        assert!(matches!("orgexample-1001".parse::<RepoSelector>()?, RepoSelector::Id(id) if id == "orgexample-1001"));
+        let RepoSelector::Filters(filters) = "@name=Example,@state=open,@latest".parse::<RepoSelector>()? else {
+            panic!("filters expected");
+        };
+        assert_eq!(filters, vec![
+            RepoFilter::ProfileName("Example".to_string()),
+            RepoFilter::State(RepositoryState::Open),
+            RepoFilter::Latest,
+        ]);
+        // commas not followed by `@` belong to the description
+        let RepoSelector::Filters(filters) = "@desc=v1.2.3, built by CI,@mine".parse::<RepoSelector>()? else {
+            panic!("filters expected");
+        };
+        assert_eq!(filters, vec![RepoFilter::Description("v1.2.3, built by CI".to_string()), RepoFilter::Mine]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for invalid in ["@", "@owner=me", "@name", "@latest=1", "@state=open,@bogus"] {
+            assert!(invalid.parse::<RepoSelector>().is_err(), "{invalid} should be rejected");
+        }
+        assert!("@id=x".parse::<ProfileSelector>().is_err());
+    }
+
+    #[test]
+    fn test_display_roundtrip() -> anyhow::Result<()> {
+        for text in ["orgexample-1001", "@name=Example,@desc=v1.2.3,@state=closed,@latest"] {
+            assert_eq!(text.parse::<RepoSelector>()?.to_string(), text);
+        }
+        assert_eq!("@name=Example".parse::<ProfileSelector>()?.to_string(), "@name=Example");
+        Ok(())
+    }
+
+    #[test]
+    fn test_select() -> anyhow::Result<()> {
+        let repos = vec![
+            repo("example-1001", "Example", "closed", "v1.0", 1000),
+            repo("example-1002", "Example", "open", "v1.1", 2000),
+            repo("example-1003", "Example", "open", "v1.1", 3000),
+            repo("other-1001", "Other", "open", "v1.1", 4000),
+        ];
+        let select = |selector: &str| -> anyhow::Result<Vec<&str>> {
+            Ok(ids(selector.parse::<RepoSelector>()?.select(&repos)?))
+        };
+        assert_eq!(select("example-1002")?, vec!["example-1002"]);
+        assert_eq!(select("example-9999")?, Vec::<&str>::new());
+        assert_eq!(select("@name=Example,@state=closed")?, vec!["example-1001"]);
+        // ambiguous - left to the caller to refuse, unless narrowed by `@latest`
+        assert_eq!(select("@name=Example,@desc=v1.1")?, vec!["example-1002", "example-1003"]);
+        assert_eq!(select("@name=Example,@desc=v1.1,@latest")?, vec!["example-1003"]);
+        assert_eq!(select("@state=released")?, Vec::<&str>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_state_never_matches() -> anyhow::Result<()> {
+        let repos = vec![repo("example-1001", "Example", "", "", 1000)];
+        let selector: RepoSelector = "@state=open".parse()?;
+        assert!(selector.select(&repos)?.is_empty());
+        Ok(())
+    }
+}
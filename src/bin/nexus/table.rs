@@ -0,0 +1,299 @@
+//! Rendering of lists as aligned tables, for humans.
+use std::cmp::Ordering;
+use std::io::IsTerminal;
+
+use chrono::{DateTime, Utc};
+use clap::Args;
+
+/// Narrowest width a truncated column can get
+const MIN_TRUNCATED_WIDTH: usize = 12;
+const ELLIPSIS: char = '…';
+const COLUMN_SEPARATOR: &str = "  ";
+
+/// ANSI color of a cell
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+}
+
+impl Color {
+    fn ansi_code(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self { text, color: None }
+    }
+}
+
+/// Column of a table showing items of type `T`
+pub struct Column<T> {
+    pub name: &'static str,
+    pub cell: fn(&T) -> Cell,
+    /// compares items for sorting by this column; by cell text when missing
+    pub compare: Option<fn(&T, &T) -> Ordering>,
+    /// the column is shortened when the table does not fit the terminal
+    pub truncate: bool,
+}
+
+impl<T> Column<T> {
+    pub fn new(name: &'static str, cell: fn(&T) -> Cell) -> Self {
+        Self { name, cell, compare: None, truncate: false }
+    }
+
+    pub fn compare(self, compare: fn(&T, &T) -> Ordering) -> Self {
+        Self { compare: Some(compare), ..self }
+    }
+
+    pub fn truncate(self) -> Self {
+        Self { truncate: true, ..self }
+    }
+}
+
+/// Table layout chosen on commandline
+#[derive(Args, Clone, Debug, Default)]
+pub struct TableOptions {
+    /// comma-separated names of columns to show
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<String>,
+    /// column to sort by; prefix with `-` for descending order
+    #[arg(long, allow_hyphen_values = true)]
+    pub sort: Option<String>,
+}
+
+/// Prints items as a table.
+///
+/// `defaults` names columns shown when [TableOptions::columns] is empty.
+pub fn print_table<T>(items: &mut [T], columns: &[Column<T>], defaults: &[&str], options: &TableOptions) -> anyhow::Result<()> {
+    for line in table_lines(items, columns, defaults, options, std::io::stdout().is_terminal())? {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Like [print_table], but to stderr - for previews which must not get mixed into structured output.
+pub fn eprint_table<T>(items: &mut [T], columns: &[Column<T>], defaults: &[&str], options: &TableOptions) -> anyhow::Result<()> {
+    for line in table_lines(items, columns, defaults, options, std::io::stderr().is_terminal())? {
+        eprintln!("{line}");
+    }
+    Ok(())
+}
+
+fn table_lines<T>(items: &mut [T], columns: &[Column<T>], defaults: &[&str], options: &TableOptions, is_terminal: bool) -> anyhow::Result<Vec<String>> {
+    let find_column = |name: &str| columns.iter().find(|column| column.name == name)
+        .ok_or_else(|| {
+            let available: Vec<&str> = columns.iter().map(|column| column.name).collect();
+            anyhow::anyhow!("Unknown column '{name}', available columns are: {}", available.join(", "))
+        });
+    if let Some(sort) = &options.sort {
+        let (name, descending) = match sort.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (sort.as_str(), false),
+        };
+        let column = find_column(name)?;
+        items.sort_by(|a, b| {
+            let ordering = match column.compare {
+                Some(compare) => compare(a, b),
+                None => (column.cell)(a).text.cmp(&(column.cell)(b).text),
+            };
+            if descending { ordering.reverse() } else { ordering }
+        });
+    }
+    let selected = if options.columns.is_empty() {
+        defaults.iter().map(|name| find_column(name)).collect::<anyhow::Result<Vec<_>>>()?
+    } else {
+        options.columns.iter().map(|name| find_column(name)).collect::<anyhow::Result<Vec<_>>>()?
+    };
+    let header: Vec<Cell> = selected.iter().map(|column| Cell::from(column.name.to_uppercase())).collect();
+    let rows: Vec<Vec<Cell>> = items.iter()
+        .map(|item| selected.iter().map(|column| (column.cell)(item)).collect())
+        .collect();
+    let truncated: Vec<bool> = selected.iter().map(|column| column.truncate).collect();
+    Ok(aligned_lines(Some(header), rows, &truncated, is_terminal))
+}
+
+/// Prints rows with aligned columns. Columns marked in `truncated` are shortened to fit terminal width.
+/// Colors are used only when printing to a terminal.
+pub fn print_aligned(header: Option<Vec<Cell>>, rows: Vec<Vec<Cell>>, truncated: &[bool]) {
+    for line in aligned_lines(header, rows, truncated, std::io::stdout().is_terminal()) {
+        println!("{line}");
+    }
+}
+
+fn aligned_lines(header: Option<Vec<Cell>>, rows: Vec<Vec<Cell>>, truncated: &[bool], is_terminal: bool) -> Vec<String> {
+    let rows: Vec<Vec<Cell>> = header.into_iter().chain(rows).collect();
+    let mut widths = column_widths(&rows);
+    if is_terminal {
+        if let Some((terminal_size::Width(terminal_width), _)) = terminal_size::terminal_size() {
+            fit_width(&mut widths, truncated, terminal_width as usize);
+        }
+    }
+    let use_colors = is_terminal && std::env::var_os("NO_COLOR").is_none();
+    rows.iter()
+        .map(|row| {
+            let last = row.len().saturating_sub(1);
+            let line: Vec<String> = row.iter().zip(&widths).enumerate()
+                .map(|(index, (cell, &width))| {
+                    let text = shorten(&cell.text, width);
+                    // no padding after the last column
+                    let padded = if index == last { text } else { format!("{text:width$}") };
+                    match cell.color {
+                        Some(color) if use_colors => format!("\x1b[{}m{padded}\x1b[0m", color.ansi_code()),
+                        _ => padded,
+                    }
+                })
+                .collect();
+            line.join(COLUMN_SEPARATOR)
+        })
+        .collect()
+}
+
+/// Width of each column, in characters
+fn column_widths(rows: &[Vec<Cell>]) -> Vec<usize> {
+    let column_count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut widths = vec![0; column_count];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.text.chars().count());
+        }
+    }
+    widths
+}
+
+/// Narrows the `truncated` columns, in their order, until the table fits `terminal_width` - or they cannot get narrower.
+fn fit_width(widths: &mut [usize], truncated: &[bool], terminal_width: usize) {
+    let total: usize = widths.iter().sum::<usize>() + COLUMN_SEPARATOR.len() * widths.len().saturating_sub(1);
+    let mut excess = total.saturating_sub(terminal_width);
+    for (width, _) in widths.iter_mut().zip(truncated).filter(|(_, &truncated)| truncated) {
+        if excess == 0 {
+            break;
+        }
+        let reduction = excess.min(width.saturating_sub(MIN_TRUNCATED_WIDTH));
+        *width -= reduction;
+        excess -= reduction;
+    }
+}
+
+fn shorten(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut shortened: String = text.chars().take(width.saturating_sub(1)).collect();
+    shortened.push(ELLIPSIS);
+    shortened
+}
+
+/// Formats byte count with binary unit, like `1.5M`
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["k", "M", "G", "T", "P"];
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size < 10.0 {
+        format!("{size:.1}{}", UNITS[unit])
+    } else {
+        format!("{size:.0}{}", UNITS[unit])
+    }
+}
+
+/// Describes how long ago was the `time`, like `3h ago`
+pub fn relative_age(time: Option<DateTime<Utc>>) -> String {
+    let Some(time) = time else {
+        return "?".to_string();
+    };
+    let seconds = (Utc::now() - time).num_seconds();
+    match seconds {
+        i64::MIN..=-1 => "in future".to_string(),
+        0..=59 => format!("{seconds}s ago"),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cell, column_widths, fit_width, human_size, shorten};
+
+    #[test]
+    fn test_column_widths() {
+        let rows = vec![
+            vec![Cell::from("ID".to_string()), Cell::from("DESCRIPTION".to_string())],
+            vec![Cell::from("orgexample-1001".to_string()), Cell::from("příliš žluťoučký".to_string())],
+            vec![Cell::from("x".to_string())],
+        ];
+        // characters, not bytes
+        assert_eq!(column_widths(&rows), vec![15, 16]);
+        assert_eq!(column_widths(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_fit_width() {
+        // 10 + 40 + 30 + two separators = 84
+        let mut widths = vec![10, 40, 30];
+        fit_width(&mut widths, &[false, true, true], 80);
+        assert_eq!(widths, vec![10, 36, 30]);
+
+        // the first truncated column is narrowed to the minimum, then the next one
+        let mut widths = vec![10, 40, 30];
+        fit_width(&mut widths, &[false, true, true], 40);
+        assert_eq!(widths, vec![10, 12, 14]);
+
+        // truncated columns do not get narrower than the minimum; others are kept
+        let mut widths = vec![10, 40, 30];
+        fit_width(&mut widths, &[false, true, true], 20);
+        assert_eq!(widths, vec![10, 12, 12]);
+
+        let mut widths = vec![10, 40];
+        fit_width(&mut widths, &[false, false], 20);
+        assert_eq!(widths, vec![10, 40]);
+
+        let mut widths = vec![10, 40];
+        fit_width(&mut widths, &[false, true], 200);
+        assert_eq!(widths, vec![10, 40]);
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("v1.2.3", 10), "v1.2.3");
+        assert_eq!(shorten("v1.2.3", 6), "v1.2.3");
+        assert_eq!(shorten("built by CI", 6), "built…");
+        assert_eq!(shorten("žluťoučký", 5), "žluť…");
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0k");
+        assert_eq!(human_size(1536), "1.5k");
+        assert_eq!(human_size(10 * 1024), "10k");
+        assert_eq!(human_size(5 << 20), "5.0M");
+        assert_eq!(human_size(3 << 30), "3.0G");
+        assert_eq!(human_size(u64::MAX), "16384P");
+    }
+}
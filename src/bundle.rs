@@ -0,0 +1,81 @@
+//! Staging bundle - a single jar with all files of a release, which Nexus turns into a closed staging repository.
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Packs all files under `root` (typically Maven-layout) into a bundle jar.
+///
+/// Files are stored flat, as Nexus expects them; the coordinates come from the POM file.
+/// A bundle holds a single GAV, so all files must be in one directory - either `root`, or the version directory under it.
+pub fn pack_bundle(root: &Path) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+    let mut gav_dir: Option<PathBuf> = None;
+    for entry in walkdir::WalkDir::new(root).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let dir = entry.path().parent().unwrap_or(root);
+        match &gav_dir {
+            None => gav_dir = Some(dir.to_path_buf()),
+            Some(gav_dir) if gav_dir != dir => {
+                anyhow::bail!("Bundle must contain a single GAV, but {} has files in {} and {}; bundle each version directory separately",
+                    root.display(), gav_dir.display(), dir.display());
+            }
+            Some(_) => {}
+        }
+        log::debug!("bundling {}", entry.path().display());
+        zip.start_file(entry.file_name().to_string_lossy(), options)?;
+        zip.write_all(&std::fs::read(entry.path())?)?;
+    }
+    if gav_dir.is_none() {
+        anyhow::bail!("No files to bundle in {}", root.display());
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::pack_bundle;
+
+    #[test]
+    fn test_pack_bundle() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let version_dir = root.join("org/example/demo/1.0");
+        std::fs::create_dir_all(&version_dir)?;
+        for name in ["demo-1.0.pom", "demo-1.0.pom.asc", "demo-1.0.jar", "demo-1.0.jar.asc"] {
+            std::fs::write(version_dir.join(name), name)?;
+        }
+        let bundle = pack_bundle(root);
+        std::fs::create_dir_all(root.join("org/example/demo/1.1"))?;
+        std::fs::write(root.join("org/example/demo/1.1/demo-1.1.pom"), "")?;
+        let multi_gav = pack_bundle(root);
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(bundle?))?;
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["demo-1.0.jar", "demo-1.0.jar.asc", "demo-1.0.pom", "demo-1.0.pom.asc"]);
+        let mut pom = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("demo-1.0.pom")?, &mut pom)?;
+        assert_eq!(pom, "demo-1.0.pom");
+
+        let message = multi_gav.unwrap_err().to_string();
+        assert!(message.contains("single GAV"), "{message}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_empty() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let result = pack_bundle(root);
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+//! Local cache of downloaded files, shared by all commands and runs.
+//!
+//! Content is stored once per SHA-1 under `objects/`; each downloaded URL has an entry under `entries/`,
+//! remembering the validators (`ETag`, `Last-Modified`) for conditional requests. The least recently used entries
+//! are evicted when the cache grows over its size limit.
+//!
+//! Entries are kept per user: content downloaded with credentials is never served to another user, or to
+//! anonymous client - each of them has its own entry, and must get it confirmed by the server.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::{Client, Method, StatusCode};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::client::{partial_path, write_verified};
+
+/// Size limit, when `NEXUS_CACHE_SIZE_MB` is not set
+pub const DEFAULT_MAX_SIZE: u64 = 2 << 30;
+
+/// Clones share the same directory and size accounting.
+#[derive(Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    max_size: u64,
+    /// total size of objects, once known
+    size: Arc<Mutex<Option<u64>>>,
+}
+
+/// What the cache holds
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    /// downloaded URLs
+    pub entries: usize,
+    /// distinct contents
+    pub objects: usize,
+    /// total size of contents, in bytes
+    pub size: u64,
+}
+
+/// A downloaded URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    url: String,
+    /// user who downloaded it; none for anonymous
+    #[serde(default)]
+    user: Option<String>,
+    sha1: String,
+    size: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// seconds since Unix epoch
+    last_used: u64,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, max_size: u64) -> Self {
+        Self { dir, max_size, size: Arc::new(Mutex::new(None)) }
+    }
+
+    /// Cache configured by `NEXUS_CACHE_DIR` and `NEXUS_CACHE_SIZE_MB`; none when the directory is not set.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Ok(dir) = std::env::var("NEXUS_CACHE_DIR") else {
+            return Ok(None);
+        };
+        let max_size = match std::env::var("NEXUS_CACHE_SIZE_MB") {
+            Ok(megabytes) => megabytes.parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("Invalid NEXUS_CACHE_SIZE_MB: '{megabytes}'"))? << 20,
+            Err(_) => DEFAULT_MAX_SIZE,
+        };
+        Ok(Some(Self::new(PathBuf::from(dir), max_size)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    fn entry_path(&self, user: Option<&str>, url: &str) -> PathBuf {
+        let key = format!("{}\n{url}", user.unwrap_or_default());
+        self.dir.join("entries").join(format!("{}.json", sha1_smol::Sha1::from(key).digest()))
+    }
+
+    fn object_path(&self, sha1: &str) -> PathBuf {
+        self.dir.join("objects").join(&sha1[..2]).join(sha1)
+    }
+
+    /// Entry of the `url` downloaded by `user`, if its content is still present
+    fn lookup(&self, user: Option<&str>, url: &str) -> Option<CacheEntry> {
+        let text = std::fs::read_to_string(self.entry_path(user, url)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&text).ok()?;
+        (entry.url == url && entry.user.as_deref() == user && self.object_path(&entry.sha1).is_file()).then_some(entry)
+    }
+
+    fn save_entry(&self, entry: &CacheEntry) -> anyhow::Result<()> {
+        let path = self.entry_path(entry.user.as_deref(), &entry.url);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let partial = partial_path(&path);
+        std::fs::write(&partial, serde_json::to_string_pretty(entry)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    /// Downloads `url` into `local_file` (atomically, like [crate::http_get_file]), served from the cache when the server
+    /// confirms that content cached for the same `user` is still valid.
+    pub(crate) async fn download(&self, client: &Client, user: Option<&str>, url: &Url, local_file: &Path) -> anyhow::Result<()> {
+        let cached = {
+            let (user, url) = (user.map(str::to_string), url.to_string());
+            self.blocking(move |cache| Ok(cache.lookup(user.as_deref(), &url))).await?
+        };
+        let mut http_request = client.request(Method::GET, url.clone());
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                http_request = http_request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                http_request = http_request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        log::debug!("downloading(GET) from: {url}");
+        let mut http_response = http_request.send().await?;
+        let partial = partial_path(local_file);
+        if let (StatusCode::NOT_MODIFIED, Some(entry)) = (http_response.status(), cached) {
+            match self.serve(entry, &partial, local_file).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    // like when evicted meanwhile by another process
+                    log::debug!("cached content of {url} is not available ({e}), downloading it again");
+                    let _ = tokio::fs::remove_file(&partial).await;
+                    http_response = client.request(Method::GET, url.clone()).send().await?;
+                }
+            }
+        }
+        let http_response = crate::check_status(http_response).await?;
+        let header = |name| http_response.headers().get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(|value| value.to_string());
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let sha1 = match write_verified(http_response, &partial).await {
+            Ok(sha1) => sha1,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e.context(format!("Failed to download {url} to {}", local_file.display())));
+            }
+        };
+        let size = tokio::fs::metadata(&partial).await?.len();
+        let entry = CacheEntry { url: url.to_string(), user: user.map(str::to_string), sha1, size, etag, last_modified, last_used: now() };
+        let file = partial.clone();
+        // failing to cache is not a reason to fail the download
+        if let Err(e) = self.blocking(move |cache| cache.store(&file, entry)).await {
+            log::warn!("Failed to cache {url}: {e}");
+        }
+        tokio::fs::rename(&partial, local_file).await?;
+        Ok(())
+    }
+
+    /// Copies cached content of `entry` into `local_file`, through `partial`.
+    async fn serve(&self, mut entry: CacheEntry, partial: &Path, local_file: &Path) -> anyhow::Result<()> {
+        log::debug!("cache hit: {}", entry.url);
+        tokio::fs::copy(self.object_path(&entry.sha1), partial).await?;
+        tokio::fs::rename(partial, local_file).await?;
+        entry.last_used = now();
+        if let Err(e) = self.blocking(move |cache| cache.save_entry(&entry)).await {
+            log::warn!("Failed to update cache entry: {e}");
+        }
+        Ok(())
+    }
+
+    /// Runs filesystem work of the cache outside of async runtime threads.
+    async fn blocking<T, F>(&self, work: F) -> anyhow::Result<T>
+        where T: Send + 'static,
+              F: FnOnce(&Cache) -> anyhow::Result<T> + Send + 'static
+    {
+        let cache = self.clone();
+        tokio::task::spawn_blocking(move || work(&cache)).await?
+    }
+
+    fn store(&self, file: &Path, entry: CacheEntry) -> anyhow::Result<()> {
+        let object_path = self.object_path(&entry.sha1);
+        let added = if object_path.is_file() {
+            0
+        } else {
+            std::fs::create_dir_all(object_path.parent().unwrap())?;
+            let partial = partial_path(&object_path);
+            std::fs::copy(file, &partial)?;
+            std::fs::rename(&partial, &object_path)?;
+            entry.size
+        };
+        self.save_entry(&entry)?;
+        let known = self.size.lock().unwrap().as_mut().map(|total| {
+            *total += added;
+            *total
+        });
+        let total = match known {
+            Some(total) => total,
+            None => {
+                // scanned without holding the lock; whoever finishes first wins
+                let scanned = self.stats()?.size;
+                *self.size.lock().unwrap().get_or_insert(scanned)
+            }
+        };
+        if total > self.max_size {
+            self.evict(self.max_size)?;
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> anyhow::Result<Vec<(PathBuf, CacheEntry)>> {
+        let dir = self.dir.join("entries");
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for dir_entry in std::fs::read_dir(dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match std::fs::read_to_string(&path).ok().and_then(|text| serde_json::from_str(&text).ok()) {
+                Some(entry) => entries.push((path, entry)),
+                None => log::debug!("ignoring unreadable cache entry {}", path.display()),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Sizes of all stored objects, by their SHA-1
+    fn objects(&self) -> anyhow::Result<HashMap<String, (PathBuf, u64)>> {
+        let dir = self.dir.join("objects");
+        let mut objects = HashMap::new();
+        if !dir.is_dir() {
+            return Ok(objects);
+        }
+        for dir_entry in walkdir::WalkDir::new(dir).min_depth(2).max_depth(2) {
+            let dir_entry = dir_entry?;
+            let name = dir_entry.file_name().to_string_lossy();
+            if dir_entry.file_type().is_file() && name.len() == 40 {
+                objects.insert(name.to_string(), (dir_entry.path().to_path_buf(), dir_entry.metadata()?.len()));
+            }
+        }
+        Ok(objects)
+    }
+
+    pub fn stats(&self) -> anyhow::Result<CacheStats> {
+        let objects = self.objects()?;
+        Ok(CacheStats {
+            entries: self.entries()?.len(),
+            objects: objects.len(),
+            size: objects.values().map(|(_, size)| size).sum(),
+        })
+    }
+
+    /// Removes least recently used entries, until the objects fit into `max_size`; objects without entries are removed, too.
+    /// Returns what was removed.
+    pub fn evict(&self, max_size: u64) -> anyhow::Result<CacheStats> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|(_, entry)| entry.last_used);
+        let mut objects = self.objects()?;
+        let mut references: HashMap<&str, usize> = HashMap::new();
+        for (_, entry) in &entries {
+            *references.entry(entry.sha1.as_str()).or_default() += 1;
+        }
+        let mut size: u64 = objects.values().map(|(_, size)| size).sum();
+        let mut removed = CacheStats::default();
+        let unreferenced: Vec<String> = objects.keys().filter(|sha1| !references.contains_key(sha1.as_str())).cloned().collect();
+        let mut remove_object = |sha1: &str, removed: &mut CacheStats, size: &mut u64| -> anyhow::Result<()> {
+            if let Some((path, object_size)) = objects.remove(sha1) {
+                std::fs::remove_file(path)?;
+                *size -= object_size;
+                removed.objects += 1;
+                removed.size += object_size;
+            }
+            Ok(())
+        };
+        for sha1 in &unreferenced {
+            remove_object(sha1, &mut removed, &mut size)?;
+        }
+        for (path, entry) in &entries {
+            if size <= max_size {
+                break;
+            }
+            std::fs::remove_file(path)?;
+            removed.entries += 1;
+            let count = references.get_mut(entry.sha1.as_str()).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                remove_object(&entry.sha1, &mut removed, &mut size)?;
+            }
+        }
+        *self.size.lock().unwrap() = Some(size);
+        if removed.entries > 0 || removed.objects > 0 {
+            log::info!("Evicted {} cache entries and {} objects ({} bytes)", removed.entries, removed.objects, removed.size);
+        }
+        Ok(removed)
+    }
+
+    /// Removes everything. Returns what was removed.
+    pub fn clean(&self) -> anyhow::Result<CacheStats> {
+        self.evict(0)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::{Response, TestServer};
+
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path();
+        let cache = Cache::new(dir.to_path_buf(), 10);
+        let file = dir.join("content");
+        for (name, content, last_used) in [("old", "aaaaaa", 1), ("new", "bbbbbb", 3), ("same", "bbbbbb", 2)] {
+            std::fs::write(&file, content)?;
+            let entry = CacheEntry {
+                url: format!("https://example.com/{name}"),
+                user: None,
+                sha1: sha1_smol::Sha1::from(content).digest().to_string(),
+                size: content.len() as u64,
+                etag: None,
+                last_modified: None,
+                last_used,
+            };
+            cache.store(&file, entry)?;
+        }
+        let stats = cache.stats()?;
+        let all = cache.lookup(None, "https://example.com/new").is_some() && cache.lookup(None, "https://example.com/same").is_some();
+        let evicted = cache.lookup(None, "https://example.com/old").is_none();
+        let cleaned = cache.clean()?;
+        assert_eq!(stats, CacheStats { entries: 2, objects: 1, size: 6 });
+        assert!(all && evicted);
+        assert_eq!(cleaned, CacheStats { entries: 2, objects: 1, size: 6 });
+        Ok(())
+    }
+
+    /// Serves `content` with an ETag, answering conditional requests with 304 - after calling `on_conditional`.
+    async fn etag_server<F: Fn() + Send + Sync + 'static>(content: &'static [u8], on_conditional: F) -> anyhow::Result<TestServer> {
+        TestServer::start(move |request| {
+            if request.headers.get("if-none-match").is_some_and(|etag| etag == "\"v1\"") {
+                on_conditional();
+                Response::status(304)
+            } else {
+                Response::ok(content).header("ETag", "\"v1\"")
+            }
+        }).await
+    }
+
+    #[tokio::test]
+    async fn test_download_miss_then_hit() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path();
+        let cache = Cache::new(dir.join("cache"), 1 << 20);
+        let server = etag_server(b"content of demo", || {}).await?;
+        let url = server.url.join("/demo.jar")?;
+        let client = Client::new();
+        let (first, second) = (dir.join("first.jar"), dir.join("second.jar"));
+
+        cache.download(&client, Some("alice"), &url, &first).await?;
+        cache.download(&client, Some("alice"), &url, &second).await?;
+        let contents = (std::fs::read(&first)?, std::fs::read(&second)?);
+        let stats = cache.stats()?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers.get("if-none-match"), None);
+        assert_eq!(requests[1].headers.get("if-none-match").map(String::as_str), Some("\"v1\""));
+        assert_eq!(contents.0, b"content of demo");
+        assert_eq!(contents.1, b"content of demo");
+        assert_eq!(stats, CacheStats { entries: 1, objects: 1, size: 15 });
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_is_not_shared_between_users() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path();
+        let cache = Cache::new(dir.join("cache"), 1 << 20);
+        let server = etag_server(b"private", || {}).await?;
+        let url = server.url.join("/private.jar")?;
+        let client = Client::new();
+
+        cache.download(&client, Some("alice"), &url, &dir.join("alice.jar")).await?;
+        cache.download(&client, None, &url, &dir.join("anonymous.jar")).await?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers.get("if-none-match"), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_again_when_cached_content_is_gone() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path();
+        let objects = dir.join("cache").join("objects");
+        let cache = Cache::new(dir.join("cache"), 1 << 20);
+        // the object disappears after the cache was consulted, like when evicted by another process
+        let server = etag_server(b"content of demo", move || std::fs::remove_dir_all(&objects).unwrap()).await?;
+        let url = server.url.join("/demo.jar")?;
+        let client = Client::new();
+        let (first, second) = (dir.join("first.jar"), dir.join("second.jar"));
+
+        cache.download(&client, None, &url, &first).await?;
+        cache.download(&client, None, &url, &second).await?;
+        let content = std::fs::read(&second)?;
+        let partials = std::fs::read_dir(dir)?
+            .filter(|entry| entry.as_ref().is_ok_and(|entry| entry.file_name().to_string_lossy().contains(".nexus-part")))
+            .count();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].headers.contains_key("if-none-match"));
+        assert_eq!(requests[2].headers.get("if-none-match"), None);
+        assert_eq!(content, b"content of demo");
+        assert_eq!(partials, 0);
+        Ok(())
+    }
+}
@@ -1,17 +1,28 @@
-use std::path::Path;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use futures_util::StreamExt;
-use reqwest::{Client, Method, Response};
-use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, HeaderMap, USER_AGENT};
+use reqwest::{Body, Client, Method, Response, StatusCode};
+use reqwest::multipart::{Form, Part};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, ETAG, HeaderMap, HeaderValue, RANGE, USER_AGENT};
 use reqwest::redirect::Policy;
 use serde::de::DeserializeOwned;
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use url::Url;
 
+use crate::cache::Cache;
+use crate::model::BundleUploadResponse;
+use crate::NexusRepository;
 use crate::restapi::{APPLICATION_JSON, APPLICATION_XML};
 use crate::util;
 
+/// Checksum header sent by some repository managers and proxies
+const X_CHECKSUM_SHA1: &str = "x-checksum-sha1";
+
 type Extractor<A> = dyn FnOnce(&str) -> anyhow::Result<A>;
 
 pub struct NexusRequest<A> {
@@ -66,6 +77,10 @@ pub struct NexusResponse<A>
 }
 
 impl<A: DeserializeOwned> NexusResponse<A> {
+    pub fn status(&self) -> reqwest::StatusCode {
+        self.raw_response.status()
+    }
+
     pub async fn parsed(self) -> anyhow::Result<A> {
         let response = crate::check_status(self.raw_response).await?;
         let text = response.text().await?;
@@ -90,6 +105,9 @@ impl<A: DeserializeOwned> NexusResponse<A> {
 pub struct NexusClient {
     base_url: Url,
     client: reqwest::Client,
+    /// who is logged in; none for anonymous client
+    user: Option<String>,
+    cache: Option<Arc<Cache>>,
 }
 
 impl NexusClient {
@@ -104,6 +122,8 @@ impl NexusClient {
         Ok(Self {
             base_url,
             client,
+            user: Some(user.to_string()),
+            cache: None,
         })
     }
 
@@ -117,9 +137,16 @@ impl NexusClient {
         Ok(Self {
             base_url,
             client,
+            user: None,
+            cache: None,
         })
     }
 
+    /// Makes [Self::download_file] use the local cache.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        Self { cache: Some(Arc::new(cache)), ..self }
+    }
+
     pub async fn execute<A: DeserializeOwned + 'static>(&self, request: NexusRequest<A>) -> anyhow::Result<NexusResponse<A>> {
         let raw_request = RawRequest {
             method: request.method,
@@ -156,49 +183,346 @@ impl NexusClient {
     }
 
     pub async fn upload_file(&self, staged_repository_id: &str, file: &Path, path: &str) -> anyhow::Result<Url> {
-        let url = self.base_url.join(&format!("/service/local/staging/deployByRepositoryId/{staged_repository_id}{path}"))?;
+        let url = self.base_url.join(&format!("/service/local/staging/deployByRepositoryId/{staged_repository_id}{}", util::encode_path(path)))?;
         let client = &self.client;
         http_put_file(client, &url, file).await?;
         Ok(url)
     }
 
-    pub async fn download_file(&self, staged_repository_id: &str, local_file: &Path, path: &str) -> anyhow::Result<Url> {
-        if let Some(dir) = local_file.parent() {
-            if !dir.exists() {
+    pub async fn upload_bytes(&self, staged_repository_id: &str, content: Vec<u8>, path: &str) -> anyhow::Result<Url> {
+        let url = self.base_url.join(&format!("/service/local/staging/deployByRepositoryId/{staged_repository_id}{}", util::encode_path(path)))?;
+        http_put_bytes(&self.client, &url, content).await?;
+        Ok(url)
+    }
+
+    /// Uploads the bundle jar (see [crate::bundle::pack_bundle]); Nexus creates and closes staging repositories from it.
+    pub async fn upload_bundle(&self, bundle: Vec<u8>, file_name: &str) -> anyhow::Result<BundleUploadResponse> {
+        let url = self.base_url.join("/service/local/staging/bundle_upload")?;
+        log::debug!("uploading bundle {file_name} ({} bytes) to: {url}", bundle.len());
+        let part = Part::bytes(bundle)
+            .file_name(file_name.to_string())
+            .mime_str("application/java-archive")?;
+        let form = Form::new().part("file", part);
+        let http_response = self.client.post(url)
+            .header(ACCEPT, APPLICATION_JSON)
+            .multipart(form)
+            .send().await?;
+        let http_response = crate::check_status(http_response).await?;
+        let text = http_response.text().await?;
+        log::trace!("parsing response text: {text}");
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Downloads a file atomically, see [http_get_file]; through the cache, when there is one.
+    /// With `create_dirs`, missing parent directories are created.
+    pub async fn download_file(&self, staged_repository_id: &str, local_file: &Path, path: &str, create_dirs: bool) -> anyhow::Result<Url> {
+        if let Some(dir) = local_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if create_dirs {
+                tokio::fs::create_dir_all(dir).await?;
+            } else if !dir.exists() {
                 anyhow::bail!("Directory does not exist: {} for file {:?}", dir.display(), local_file.file_name());
             }
         }
-        let url = self.base_url.join(&format!("/service/local/repositories/{staged_repository_id}/content{path}"))?;
-        http_get_file(&self.client, &url, local_file).await?;
+        let url = self.content_url(staged_repository_id, path)?;
+        match &self.cache {
+            None => http_get_file(&self.client, &url, local_file).await?,
+            Some(cache) => cache.download(&self.client, self.user.as_deref(), &url, local_file).await?,
+        }
+        Ok(url)
+    }
+
+    /// Starts download of a file; with `range`, only that part of content is requested.
+    pub async fn get_file(&self, repository_id: &str, path: &str, range: Option<ByteRange>) -> anyhow::Result<Response> {
+        let url = self.content_url(repository_id, path)?;
+        http_get(&self.client, &url, range).await
+    }
+
+    /// Writes content of a file, or just its `range`, to the `writer`. Returns number of bytes written.
+    pub async fn read_file<W: AsyncWrite + Unpin>(&self, repository_id: &str, path: &str, range: Option<ByteRange>, writer: &mut W) -> anyhow::Result<u64> {
+        let http_response = self.get_file(repository_id, path, range).await?;
+        // server may ignore the range and send whole content
+        let range = range.filter(|_| http_response.status() != StatusCode::PARTIAL_CONTENT);
+        let mut position = 0;
+        let mut written = 0;
+        let mut stream = http_response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            let chunk_start = position;
+            position += chunk.len() as u64;
+            let chunk = match range {
+                None => &chunk[..],
+                Some(ByteRange { start, end }) => {
+                    let from = start.saturating_sub(chunk_start).min(chunk.len() as u64) as usize;
+                    let to = end.map_or(chunk.len() as u64, |end| (end + 1).saturating_sub(chunk_start).min(chunk.len() as u64)) as usize;
+                    &chunk[from..to.max(from)]
+                }
+            };
+            writer.write_all(chunk).await?;
+            written += chunk.len() as u64;
+            if range.is_some_and(|range| range.end.is_some_and(|end| position > end)) {
+                break;
+            }
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Uploads a local file to `path` in a repository, addressed as in [crate::NexusRepository::nexus_readwrite].
+    pub async fn put_file(&self, repository_id: &str, path: &str, file: &Path) -> anyhow::Result<Url> {
+        let repo_path = NexusRepository::nexus_readwrite(repository_id).repo_path;
+        let url = self.base_url.join(&format!("{repo_path}{}", util::encode_path(path)))?;
+        http_put_file(&self.client, &url, file).await?;
         Ok(url)
     }
+
+    /// Uploads content of `response` as it arrives, without buffering it whole, to `path` in a repository.
+    /// The repository is addressed as in [crate::NexusRepository::nexus_readwrite].
+    pub async fn upload_response(&self, repository_id: &str, path: &str, response: Response) -> anyhow::Result<Url> {
+        let repo_path = NexusRepository::nexus_readwrite(repository_id).repo_path;
+        let url = self.base_url.join(&format!("{repo_path}{}", util::encode_path(path)))?;
+        log::debug!("uploading(PUT) to: {url}");
+        let http_request = self.client.request(Method::PUT, url.clone());
+        let http_request = match response.content_length() {
+            None => http_request,
+            Some(content_length) => http_request.header(CONTENT_LENGTH, content_length),
+        };
+        let http_response = http_request
+            .body(Body::wrap_stream(response.bytes_stream()))
+            .send().await?;
+        crate::check_status(http_response).await?;
+        Ok(url)
+    }
+
+    /// Headers of a file, as returned by HTTP `HEAD` request.
+    pub async fn head_file(&self, repository_id: &str, path: &str) -> anyhow::Result<HeaderMap> {
+        let url = self.content_url(repository_id, path)?;
+        log::debug!("requesting: HEAD {url}");
+        let http_response = self.client.head(url).send().await?;
+        let http_response = crate::check_status(http_response).await?;
+        Ok(http_response.headers().clone())
+    }
+
+    fn content_url(&self, repository_id: &str, path: &str) -> anyhow::Result<Url> {
+        Ok(self.base_url.join(&format!("/service/local/repositories/{repository_id}/content{}", util::encode_path(path)))?)
+    }
+}
+
+/// Part of content to read, like in HTTP `Range` header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    /// last byte to read (inclusive); until end of content when missing
+    pub end: Option<u64>,
+}
+
+impl Display for ByteRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            None => write!(f, "{}-", self.start),
+            Some(end) => write!(f, "{}-{end}", self.start),
+        }
+    }
+}
+
+/// Syntax: `<start>-[<end>]`, like `0-1023` or `4096-`
+impl FromStr for ByteRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((start, end)) = s.split_once('-') else {
+            anyhow::bail!("Byte range must look like '<start>-[<end>]': '{s}'");
+        };
+        let start = start.parse()?;
+        let end = if end.is_empty() { None } else { Some(end.parse()?) };
+        if end.is_some_and(|end| end < start) {
+            anyhow::bail!("Byte range ends before its start: '{s}'");
+        }
+        Ok(Self { start, end })
+    }
 }
 
 pub async fn http_put_file(client: &Client, url: &Url, file: &Path) -> anyhow::Result<()> {
     let mut file = File::open(file).await?;
     let mut vec = Vec::new();
     file.read_to_end(&mut vec).await?;
-    let length = file.metadata().await?.len();
+    http_put_bytes(client, url, vec).await
+}
+
+pub async fn http_put_bytes(client: &Client, url: &Url, content: Vec<u8>) -> anyhow::Result<()> {
     log::debug!("uploading(PUT) to: {url}");
     let http_req = client.request(Method::PUT, url.clone())
-        .header(CONTENT_LENGTH, length)
-        .body(vec)
+        .header(CONTENT_LENGTH, content.len())
+        .body(content)
         .build()?;
     let http_response = client.execute(http_req).await?;
     crate::check_status(http_response).await?;
     Ok(())
 }
 
-pub async fn http_get_file(client: &Client, url: &Url, local_file: &Path) -> anyhow::Result<()> {
+pub async fn http_get(client: &Client, url: &Url, range: Option<ByteRange>) -> anyhow::Result<Response> {
     log::debug!("downloading(GET) from: {url}");
-    let http_response = client.request(Method::GET, url.clone())
-        .send().await?;
-    let http_response = crate::check_status(http_response).await?;
+    let http_request = client.request(Method::GET, url.clone());
+    let http_request = match range {
+        None => http_request,
+        Some(range) => {
+            log::debug!("- range: {range}");
+            http_request.header(RANGE, format!("bytes={range}"))
+        }
+    };
+    let http_response = http_request.send().await?;
+    crate::check_status(http_response).await
+}
+
+/// Marks temporary files, renamed to their final name once complete
+pub(crate) const PARTIAL_SUFFIX: &str = ".nexus-part";
+
+/// Sibling of `path`, where its content is written before it is complete. The name is unique, so that concurrent
+/// writers of the same file (other tasks or processes) never write into each other's temporary file.
+pub(crate) fn partial_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(format!("{PARTIAL_SUFFIX}-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+    PathBuf::from(partial)
+}
+
+/// SHA-1 of the content, as announced by the server: in `X-Checksum-Sha1` header, or in Nexus-style `ETag` like `"{SHA1{...}}"`.
+pub(crate) fn announced_sha1(headers: &HeaderMap) -> Option<String> {
+    let header = |name| headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+    if let Some(sha1) = header(X_CHECKSUM_SHA1) {
+        return Some(sha1.to_lowercase());
+    }
+    let sha1 = header(ETAG.as_str())?
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .strip_prefix("{SHA1{")?
+        .strip_suffix("}}")?;
+    Some(sha1.to_lowercase())
+}
+
+/// Downloads `url` into `local_file`, which appears only once complete: content goes to a temporary file first,
+/// which is synced to disk, verified against announced length and SHA-1, and then renamed.
+pub async fn http_get_file(client: &Client, url: &Url, local_file: &Path) -> anyhow::Result<()> {
+    let http_response = http_get(client, url, None).await?;
+    let partial = partial_path(local_file);
+    log::trace!("Creating file: {}", partial.display());
+    if let Err(e) = write_verified(http_response, &partial).await {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e.context(format!("Failed to download {url} to {}", local_file.display())));
+    }
+    tokio::fs::rename(&partial, local_file).await?;
+    Ok(())
+}
+
+/// Writes the content into `file_path`, and verifies it. Returns its SHA-1.
+pub(crate) async fn write_verified(http_response: Response, file_path: &Path) -> anyhow::Result<String> {
+    let expected_size = http_response.content_length();
+    let expected_sha1 = announced_sha1(http_response.headers());
     let mut stream = http_response.bytes_stream();
-    log::trace!("Creating file: {}", local_file.display());
-    let mut file = File::create(local_file).await?;
+    let mut file = File::create(file_path).await?;
+    let mut hasher = sha1_smol::Sha1::new();
+    let mut size = 0;
     while let Some(chunk) = stream.next().await {
-        file.write(&chunk?).await?;
+        let chunk = chunk?;
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+    if let Some(expected_size) = expected_size {
+        if size != expected_size {
+            anyhow::bail!("Received {size} bytes, expected {expected_size}");
+        }
+    }
+    let sha1 = hasher.digest().to_string();
+    if let Some(expected_sha1) = expected_sha1 {
+        if sha1 != expected_sha1 {
+            anyhow::bail!("SHA-1 mismatch: received {sha1}, expected {expected_sha1}");
+        }
+    }
+    Ok(sha1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use reqwest::header::{ETAG, HeaderMap, HeaderValue};
+
+    use crate::test_server::{Response, TestServer};
+
+    use super::{announced_sha1, ByteRange, http_get_file, partial_path};
+
+    #[test]
+    fn test_partial_path_is_unique() {
+        let path = Path::new("/tmp/demo-1.0.jar");
+        let (first, second) = (partial_path(path), partial_path(path));
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        assert!(first.to_string_lossy().starts_with("/tmp/demo-1.0.jar.nexus-part-"));
+    }
+
+    #[test]
+    fn test_byte_range() -> anyhow::Result<()> {
+        let range: ByteRange = "100-199".parse()?;
+        assert_eq!(range, ByteRange { start: 100, end: Some(199) });
+        let range: ByteRange = "4096-".parse()?;
+        assert_eq!(range.end, None);
+        assert_eq!(range.to_string(), "4096-");
+        assert!("200-100".parse::<ByteRange>().is_err());
+        assert!("100".parse::<ByteRange>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_announced_sha1() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(announced_sha1(&headers), None);
+        headers.insert(ETAG, HeaderValue::from_static("\"{SHA1{7D8E5C0A1B2C3D4E5F60718293A4B5C6D7E8F901}}\""));
+        assert_eq!(announced_sha1(&headers).as_deref(), Some("7d8e5c0a1b2c3d4e5f60718293a4b5c6d7e8f901"));
+        headers.insert(ETAG, HeaderValue::from_static("\"5d41402abc4b2a76\""));
+        assert_eq!(announced_sha1(&headers), None);
+        headers.insert("X-Checksum-Sha1", HeaderValue::from_static("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"));
+        assert_eq!(announced_sha1(&headers).as_deref(), Some("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"));
+    }
+
+    /// Downloads what the `response` gives; returns the outcome, and names of files left in the target directory.
+    async fn download(response: fn() -> Response) -> anyhow::Result<(anyhow::Result<()>, Vec<String>)> {
+        let server = TestServer::start(move |_| response()).await?;
+        let temp = tempfile::tempdir()?;
+        let dir = temp.path();
+        let result = http_get_file(&reqwest::Client::new(), &server.url.join("/demo.jar")?, &dir.join("demo.jar")).await;
+        let mut files: Vec<String> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .collect::<Result<_, _>>()?;
+        files.sort();
+        Ok((result, files))
+    }
+
+    #[tokio::test]
+    async fn test_download_verified() -> anyhow::Result<()> {
+        // SHA-1 of "content"
+        let (result, files) = download(|| Response::ok(b"content")
+            .header("X-Checksum-Sha1", "040F06FD774092478D450774F5BA30C5DA78ACC8")).await?;
+        result?;
+        assert_eq!(files, vec!["demo.jar"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_length_mismatch() -> anyhow::Result<()> {
+        let (result, files) = download(|| Response::ok(b"truncated").header("Content-Length", "100")).await?;
+        assert!(result.is_err());
+        assert!(files.is_empty(), "left behind: {files:?}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_checksum_mismatch() -> anyhow::Result<()> {
+        let (result, files) = download(|| Response::ok(b"content")
+            .header("ETag", "\"{SHA1{0000000000000000000000000000000000000000}}\"")).await?;
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(message.contains("SHA-1 mismatch"), "{message}");
+        assert!(files.is_empty(), "left behind: {files:?}");
+        Ok(())
     }
-    Ok(())
 }
@@ -5,6 +5,7 @@ use reqwest::Response;
 
 pub use auth::get_credentials;
 pub use auth::nexus_url;
+pub use client::ByteRange;
 pub use client::http_get_file;
 pub use client::http_put_file;
 pub use client::NexusClient;
@@ -14,11 +15,18 @@ pub use restapi::{NexusRepository, StagingProfiles, StagingRepositories};
 use restapi::APPLICATION_JSON;
 
 pub mod model;
+pub mod bundle;
+pub mod cache;
 mod auth;
 mod util;
 mod client;
 mod restapi;
 pub mod remote_sync;
+pub mod mirror;
+pub mod signing;
+pub mod validate;
+#[cfg(test)]
+mod test_server;
 
 async fn check_status(response: Response) -> anyhow::Result<Response> {
     let status = response.status();
@@ -27,16 +35,14 @@ async fn check_status(response: Response) -> anyhow::Result<Response> {
     }
     let content_type = response.headers().get(CONTENT_TYPE);
     match content_type {
-        None => {}
-        Some(content_type) => {
-            if content_type.to_str().unwrap().starts_with(APPLICATION_JSON) {
-                let text = response.text().await?;
-                anyhow::bail!("HTTP {} {}: with this JSON info: {text}",
-                        status.as_str(),
-                        status.canonical_reason().unwrap_or(""),
-                    );
-            }
+        Some(content_type) if content_type.to_str().unwrap().starts_with(APPLICATION_JSON) => {
+            let text = response.text().await?;
+            anyhow::bail!("HTTP {} {}: with this JSON info: {text}",
+                    status.as_str(),
+                    status.canonical_reason().unwrap_or(""),
+                );
         }
+        _ => {}
     }
     let text = response.text().await?;
     anyhow::bail!("HTTP {} {}: {text}",
@@ -82,4 +88,35 @@ mod tests {
         println!("serde_xml_rs: {}", serde_xml_rs::to_string(&body)?);
         Ok(())
     }
+
+    #[test]
+    fn test_bulk_request() -> anyhow::Result<()> {
+        let repository_ids = vec!["orgexample-1001".to_string(), "orgexample-1002".to_string()];
+        let request = StagingRepositories::bulk_promote(&repository_ids, "v1.2.3 \"final\"");
+        assert_eq!(request.method, reqwest::Method::POST);
+        assert_eq!(request.url_suffix, "/service/local/staging/bulk/promote");
+        let body: serde_json::Value = serde_json::from_str(&request.body)?;
+        assert_eq!(body, serde_json::json!({"data": {
+            "stagedRepositoryIds": ["orgexample-1001", "orgexample-1002"],
+            "description": "v1.2.3 \"final\"",
+        }}));
+
+        let request = StagingRepositories::bulk_drop(&repository_ids[..1], "");
+        assert_eq!(request.url_suffix, "/service/local/staging/bulk/drop");
+        let body: serde_json::Value = serde_json::from_str(&request.body)?;
+        assert_eq!(body, serde_json::json!({"data": {"stagedRepositoryIds": ["orgexample-1001"]}}));
+        Ok(())
+    }
+
+    #[test]
+    fn test_promote_request() -> anyhow::Result<()> {
+        let request = crate::StagingProfiles::promote("12a3b4", "orgexample-1001");
+        let body: serde_json::Value = serde_json::from_str(&request.body)?;
+        assert_eq!(body, serde_json::json!({"data": {"stagedRepositoryId": "orgexample-1001"}}));
+        let request = crate::StagingProfiles::promote_with_description("12a3b4", "orgexample-1001", "v1.2.3");
+        assert_eq!(request.url_suffix, "/service/local/staging/profiles/12a3b4/promote");
+        let body: serde_json::Value = serde_json::from_str(&request.body)?;
+        assert_eq!(body, serde_json::json!({"data": {"stagedRepositoryId": "orgexample-1001", "description": "v1.2.3"}}));
+        Ok(())
+    }
 }
@@ -0,0 +1,343 @@
+//! Rsync-like mirroring of a remote tree into local directory, or vice versa.
+//!
+//! The local directory keeps a state file with timestamps of mirrored files, so that unchanged files are recognized
+//! without transferring them.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{NexusClient, NexusRepository};
+use crate::client::{partial_path, PARTIAL_SUFFIX};
+use crate::remote_sync::{list_local_files, list_remote_files, split_results};
+
+/// State of mirroring from remote, stored in the local directory
+pub const DOWNLOAD_STATE_FILE: &str = ".nexus-mirror.json";
+/// State of mirroring to remote, stored in the local directory
+pub const UPLOAD_STATE_FILE: &str = ".nexus-mirror-up.json";
+/// How many files are transferred at once
+pub(crate) const CONCURRENT_TRANSFERS: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct MirrorOptions {
+    /// remove files of target, which are missing in source
+    pub delete: bool,
+    /// when not empty, only files matching any of these are mirrored
+    pub include: Vec<glob::Pattern>,
+    /// files matching any of these are not mirrored, nor deleted
+    pub exclude: Vec<glob::Pattern>,
+    /// only report what would be done
+    pub dry_run: bool,
+}
+
+impl MirrorOptions {
+    /// Matches a path relative to mirrored roots
+    fn selects(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path)))
+            && !self.exclude.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAction {
+    Download,
+    Upload,
+    DeleteLocal,
+    DeleteRemote,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirrorChange {
+    pub action: MirrorAction,
+    /// relative to mirrored roots, with `/` as separator
+    pub path: String,
+}
+
+/// Last known version of each mirrored file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MirrorState {
+    files: BTreeMap<String, FileStamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileStamp {
+    size: u64,
+    /// remote `lastModified`, or local modification time
+    last_modified: String,
+}
+
+impl MirrorState {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        match serde_json::from_str(&std::fs::read_to_string(path)?) {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                log::warn!("Ignoring unreadable mirror state {}: {e}", path.display());
+                Ok(Self::default())
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let partial = partial_path(path);
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+}
+
+/// Local files, without the mirroring state and leftovers of interrupted transfers
+fn local_files(local_root: &Path) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    if !local_root.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let mut files = list_local_files(local_root)?;
+    files.retain(|path, _| path != DOWNLOAD_STATE_FILE && path != UPLOAD_STATE_FILE && !path.contains(PARTIAL_SUFFIX));
+    Ok(files)
+}
+
+/// Makes local directory a copy of the remote tree. Changed files are replaced only once completely downloaded.
+pub async fn mirror_download(nexus: &NexusClient, repo_id: &str, remote_root: &str, local_root: &Path, options: &MirrorOptions) -> anyhow::Result<Vec<MirrorChange>> {
+    let remote_files = list_remote_files(nexus, repo_id, remote_root).await?;
+    if !options.dry_run {
+        std::fs::create_dir_all(local_root)?;
+    }
+    let local_files = local_files(local_root)?;
+    let state_path = local_root.join(DOWNLOAD_STATE_FILE);
+    let mut state = MirrorState::load(&state_path)?;
+    let mut changes = Vec::new();
+    let mut downloads = Vec::new();
+    for (path, entry) in remote_files.iter().filter(|(path, _)| options.selects(path)) {
+        let stamp = FileStamp {
+            size: entry.size_on_disk.max(0) as u64,
+            last_modified: entry.last_modified.clone(),
+        };
+        let local_path = local_root.join(path);
+        let unchanged = local_files.contains_key(path)
+            && state.files.get(path) == Some(&stamp)
+            && local_path.metadata().is_ok_and(|metadata| metadata.len() == stamp.size);
+        if unchanged {
+            continue;
+        }
+        changes.push(MirrorChange { action: MirrorAction::Download, path: path.clone() });
+        downloads.push((path.clone(), format!("{remote_root}{path}"), local_path, stamp));
+    }
+    if options.delete {
+        for path in local_files.keys().filter(|path| options.selects(path) && !remote_files.contains_key(*path)) {
+            changes.push(MirrorChange { action: MirrorAction::DeleteLocal, path: path.clone() });
+        }
+    }
+    if options.dry_run {
+        return Ok(changes);
+    }
+    let results: Vec<anyhow::Result<(String, FileStamp)>> = stream::iter(downloads)
+        .map(|(path, remote_path, local_path, stamp)| async move {
+            nexus.download_file(repo_id, &local_path, &remote_path, true).await?;
+            log::info!("* ::/{repo_id}{remote_path} -> {}", local_path.display());
+            Ok((path, stamp))
+        })
+        .buffer_unordered(CONCURRENT_TRANSFERS)
+        .collect().await;
+    let (transferred, error) = split_results(results);
+    state.files.extend(transferred);
+    let mut deleted = Ok(());
+    for change in changes.iter().filter(|change| change.action == MirrorAction::DeleteLocal) {
+        if let Err(e) = std::fs::remove_file(local_root.join(&change.path)) {
+            deleted = Err(e);
+            break;
+        }
+        state.files.remove(&change.path);
+        log::warn!("Removed: {}", change.path);
+    }
+    state.files.retain(|path, _| remote_files.contains_key(path));
+    // record progress even when some downloads or deletions failed, so that they are not repeated
+    state.save(&state_path)?;
+    deleted?;
+    error.map_or(Ok(changes), Err)
+}
+
+/// Makes remote tree a copy of local directory. Files are uploaded when they are missing remotely,
+/// differ in size, or were modified locally since the last mirroring.
+pub async fn mirror_upload(nexus: &NexusClient, repo_id: &str, remote_root: &str, local_root: &Path, options: &MirrorOptions) -> anyhow::Result<Vec<MirrorChange>> {
+    let remote_files = list_remote_files(nexus, repo_id, remote_root).await?;
+    let local_files = local_files(local_root)?;
+    let state_path = local_root.join(UPLOAD_STATE_FILE);
+    let mut state = MirrorState::load(&state_path)?;
+    let mut changes = Vec::new();
+    let mut uploads = Vec::new();
+    for (path, local_path) in local_files.iter().filter(|(path, _)| options.selects(path)) {
+        let metadata = local_path.metadata()?;
+        let modified: chrono::DateTime<chrono::Utc> = metadata.modified()?.into();
+        let stamp = FileStamp {
+            size: metadata.len(),
+            last_modified: modified.to_rfc3339(),
+        };
+        let unchanged = remote_files.get(path).is_some_and(|remote| remote.size_on_disk == stamp.size as i64)
+            && state.files.get(path) == Some(&stamp);
+        if unchanged {
+            continue;
+        }
+        changes.push(MirrorChange { action: MirrorAction::Upload, path: path.clone() });
+        uploads.push((path.clone(), format!("{remote_root}{path}"), local_path.clone(), stamp));
+    }
+    if options.delete {
+        for path in remote_files.keys().filter(|path| options.selects(path) && !local_files.contains_key(*path)) {
+            changes.push(MirrorChange { action: MirrorAction::DeleteRemote, path: path.clone() });
+        }
+    }
+    if options.dry_run {
+        return Ok(changes);
+    }
+    let results: Vec<anyhow::Result<(String, FileStamp)>> = stream::iter(uploads)
+        .map(|(path, remote_path, local_path, stamp)| async move {
+            nexus.put_file(repo_id, &remote_path, &local_path).await?;
+            log::info!("* {} -> ::/{repo_id}{remote_path}", local_path.display());
+            Ok((path, stamp))
+        })
+        .buffer_unordered(CONCURRENT_TRANSFERS)
+        .collect().await;
+    let (transferred, error) = split_results(results);
+    state.files.extend(transferred);
+    let repository = NexusRepository::nexus_readwrite(repo_id);
+    let mut deleted = Ok(());
+    for change in changes.iter().filter(|change| change.action == MirrorAction::DeleteRemote) {
+        let remote_path = format!("{remote_root}{}", change.path);
+        if let Err(e) = async { nexus.execute(repository.delete(&remote_path)).await?.check().await }.await {
+            deleted = Err(e);
+            break;
+        }
+        state.files.remove(&change.path);
+        log::warn!("Removed: ::/{repo_id}{remote_path}");
+    }
+    state.files.retain(|path, _| local_files.contains_key(path));
+    // record progress even when a deletion failed, so that finished uploads are not repeated
+    state.save(&state_path)?;
+    deleted?;
+    error.map_or(Ok(changes), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::{repository, Response, TestServer};
+
+    use super::*;
+
+    const FILES: [(&str, &str); 3] = [
+        ("org/example/demo/1.0/demo-1.0.pom", "<project/>"),
+        ("org/example/demo/1.0/demo-1.0.jar", "jar content"),
+        ("org/example/demo/1.1-SNAPSHOT/demo-1.1-SNAPSHOT.jar", "snapshot"),
+    ];
+
+    fn paths(changes: &[MirrorChange], action: MirrorAction) -> Vec<&str> {
+        let mut paths: Vec<&str> = changes.iter()
+            .filter(|change| change.action == action)
+            .map(|change| change.path.as_str())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_selects() -> anyhow::Result<()> {
+        let options = MirrorOptions {
+            include: vec![glob::Pattern::new("org/example/**")?],
+            exclude: vec![glob::Pattern::new("**/*-SNAPSHOT/**")?],
+            ..MirrorOptions::default()
+        };
+        assert!(options.selects("org/example/demo/1.0/demo-1.0.jar"));
+        assert!(!options.selects("org/example/demo/1.1-SNAPSHOT/demo-1.1-SNAPSHOT.jar"));
+        assert!(!options.selects("com/example/demo/1.0/demo-1.0.jar"));
+        assert!(MirrorOptions::default().selects("anything"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_files_skip_state() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("org/example"))?;
+        std::fs::write(root.join("org/example/demo.pom"), "<project/>")?;
+        std::fs::write(root.join("org/example/demo.jar.nexus-part-1234-0"), "")?;
+        MirrorState::default().save(&root.join(DOWNLOAD_STATE_FILE))?;
+        let files = local_files(root)?;
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["org/example/demo.pom"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mirror_download() -> anyhow::Result<()> {
+        let server = TestServer::start(repository("releases", &FILES)).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path().join("mirror");
+        let options = MirrorOptions {
+            exclude: vec![glob::Pattern::new("**/*-SNAPSHOT/**")?],
+            ..MirrorOptions::default()
+        };
+
+        let planned = mirror_download(&nexus, "releases", "/org/example/", &root, &MirrorOptions { dry_run: true, ..options.clone() }).await?;
+        let nothing_written = !root.exists();
+        let downloaded = mirror_download(&nexus, "releases", "/org/example/", &root, &options).await?;
+        let jar = std::fs::read_to_string(root.join("demo/1.0/demo-1.0.jar"))?;
+        let again = mirror_download(&nexus, "releases", "/org/example/", &root, &options).await?;
+        std::fs::write(root.join("demo/1.0/stale.txt"), "")?;
+        let deleted = mirror_download(&nexus, "releases", "/org/example/", &root, &MirrorOptions { delete: true, ..options }).await?;
+        let stale_exists = root.join("demo/1.0/stale.txt").exists();
+
+        assert_eq!(paths(&planned, MirrorAction::Download), vec!["demo/1.0/demo-1.0.jar", "demo/1.0/demo-1.0.pom"]);
+        assert!(nothing_written);
+        assert_eq!(downloaded, planned);
+        assert_eq!(jar, "jar content");
+        assert_eq!(again, vec![]);
+        assert_eq!(deleted, vec![MirrorChange { action: MirrorAction::DeleteLocal, path: "demo/1.0/stale.txt".to_string() }]);
+        assert!(!stale_exists);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mirror_upload() -> anyhow::Result<()> {
+        let server = TestServer::start(repository("releases", &FILES[..1])).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("demo/1.0"))?;
+        std::fs::write(root.join("demo/1.0/demo-1.0.jar"), "jar content")?;
+        let options = MirrorOptions { delete: true, dry_run: true, ..MirrorOptions::default() };
+
+        let planned = mirror_upload(&nexus, "releases", "/org/example/", root, &options).await?;
+
+        assert_eq!(paths(&planned, MirrorAction::Upload), vec!["demo/1.0/demo-1.0.jar"]);
+        assert_eq!(paths(&planned, MirrorAction::DeleteRemote), vec!["demo/1.0/demo-1.0.pom"]);
+        assert!(server.requests().iter().all(|request| request.method == "GET"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mirror_upload_saves_state_on_failed_delete() -> anyhow::Result<()> {
+        let handler = repository("releases", &FILES[..1]);
+        let server = TestServer::start(move |request| {
+            if request.method == "DELETE" {
+                Response::status(500)
+            } else {
+                handler(request)
+            }
+        }).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("demo/1.0"))?;
+        std::fs::write(root.join("demo/1.0/demo-1.0.jar"), "jar content")?;
+        let options = MirrorOptions { delete: true, ..MirrorOptions::default() };
+
+        let result = mirror_upload(&nexus, "releases", "/org/example/", root, &options).await;
+        let state = MirrorState::load(&root.join(UPLOAD_STATE_FILE))?;
+
+        assert!(result.is_err());
+        assert_eq!(state.files.keys().collect::<Vec<_>>(), vec!["demo/1.0/demo-1.0.jar"]);
+        Ok(())
+    }
+}
@@ -1,6 +1,94 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
+
+/// Defines an enum of string values known to us, keeping unknown values in variant `Other`,
+/// so that they survive deserialization and serialization unchanged.
+macro_rules! string_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $value:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($variant,)+
+            Other(String),
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                $(if value.eq_ignore_ascii_case($value) {
+                    return Self::$variant;
+                })+
+                Self::Other(value)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.to_string()
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from(s.to_string()))
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => $value,)+
+                    Self::Other(value) => value,
+                })
+            }
+        }
+    };
+}
+
+string_enum! {
+    /// State of a staging repository
+    RepositoryState {
+        Open = "open",
+        Closed = "closed",
+        Released = "released",
+    }
+}
+
+string_enum! {
+    /// Kind of artifacts accepted by a repository
+    RepositoryPolicy {
+        Release = "release",
+        Snapshot = "snapshot",
+        Mixed = "mixed",
+    }
+}
+
+string_enum! {
+    /// How the staging profile can be used to create staging repositories
+    ProfileMode {
+        Both = "BOTH",
+        Deploy = "DEPLOY",
+        Upload = "UPLOAD",
+    }
+}
+
+/// Converts Nexus timestamp, in milliseconds since epoch
+fn datetime_from_millis(millis: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis)
+}
+
+/// Parses ISO-8601 timestamps as used in staging activities, like `2023-04-16T10:38:39.123Z`
+fn datetime_from_rfc3339(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text).ok()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize)]
 #[serde(rename = "promoteRequest")]
@@ -19,8 +107,22 @@ pub struct PromoteRequestData {
     pub target_repository_id: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+/// Request for the bulk operations `/service/local/staging/bulk/{close,promote,drop}`
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+pub struct StagingBulkRequest {
+    pub data: StagingBulkRequestData,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct StagingBulkRequestData {
+    pub staged_repository_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct DirEntry {
     #[serde(rename = "resourceURI")]
     pub resource_uri: String,
@@ -31,6 +133,40 @@ pub struct DirEntry {
     pub size_on_disk: i64,
 }
 
+impl DirEntry {
+    /// Parses [Self::last_modified], which looks like `2023-04-14 10:22:33.0 UTC`
+    pub fn last_modified_at(&self) -> Option<DateTime<Utc>> {
+        let text = self.last_modified.strip_suffix(" UTC")?;
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").ok()
+            .map(|datetime| datetime.and_utc())
+    }
+}
+
+/// Metadata of a stored file, as returned with `?describe=info`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StorageItemInfo {
+    pub repository_id: String,
+    pub repository_path: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub uploader: String,
+    /// milliseconds since epoch
+    pub uploaded: i64,
+    /// milliseconds since epoch
+    pub last_changed: i64,
+    pub sha1_hash: String,
+    pub md5_hash: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StorageItemInfo {
+    pub fn last_changed_at(&self) -> Option<DateTime<Utc>> {
+        datetime_from_millis(self.last_changed)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Deserialize)]
 #[serde(rename = "promoteResponse")]
 pub struct PromoteResponse {
@@ -44,6 +180,23 @@ pub struct PromoteResponseData {
     pub description: Option<String>,
 }
 
+/// Response of the `bundle_upload` - one URI for each staging repository created from the bundle
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleUploadResponse {
+    pub repository_uris: Vec<String>,
+}
+
+impl BundleUploadResponse {
+    /// Staging repository IDs, extracted from the last segment of each URI
+    pub fn repository_ids(&self) -> Vec<String> {
+        self.repository_uris.iter()
+            .map(|uri| uri.trim_end_matches('/'))
+            .map(|uri| uri.rsplit('/').next().unwrap_or(uri).to_string())
+            .collect()
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NexusResponseData {
@@ -57,36 +210,66 @@ pub struct ListOfObjects<T> {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct StagingProfileRepository {
     pub profile_id: String,
     pub profile_name: String,
     pub profile_type: String,
     pub repository_id: String,
+    /// raw state as sent by the server, see [Self::state]
     #[serde(rename = "type")]
     pub repository_type: String,
+    /// raw policy as sent by the server, see [Self::repository_policy]
     pub policy: String,
     pub user_id: String,
-    pub user_agent: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
     pub ip_address: String,
     #[serde(rename = "repositoryURI")]
     pub repository_uri: String,
     pub created: String,
     pub created_date: String,
-    pub created_timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_timestamp: Option<i64>,
     pub updated: String,
     pub updated_date: String,
-    pub updated_timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_timestamp: Option<i64>,
     pub description: String,
     pub provider: String,
     pub release_repository_id: String,
     pub release_repository_name: String,
-    pub notifications: i64,
-    pub transitioning: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<i64>,
+    /// unknown when missing; callers must not assume the repository is idle then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transitioning: Option<bool>,
+    /// properties unknown to this library, preserved for re-serialization
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StagingProfileRepository {
+    /// Unknown when the server did not send it
+    pub fn state(&self) -> Option<RepositoryState> {
+        (!self.repository_type.is_empty()).then(|| RepositoryState::from(self.repository_type.clone()))
+    }
+
+    pub fn repository_policy(&self) -> RepositoryPolicy {
+        RepositoryPolicy::from(self.policy.clone())
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_timestamp.and_then(datetime_from_millis)
+    }
+
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.updated_timestamp.and_then(datetime_from_millis)
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct StagingProfile {
     #[serde(rename = "resourceURI")]
     pub resource_uri: String,
@@ -106,24 +289,37 @@ pub struct StagingProfile {
     pub close_rule_sets: Vec<String>,
     pub promote_rule_sets: Vec<Value>,
     pub promotion_target_repository: String,
+    /// raw mode as sent by the server, see [Self::profile_mode]
     pub mode: String,
     pub finish_notify_creator: bool,
     pub promotion_notify_creator: bool,
     pub drop_notify_creator: bool,
     pub auto_staging_disabled: bool,
     pub repositories_searchable: bool,
-    pub properties: Properties,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<Properties>,
+    /// properties unknown to this library, preserved for re-serialization
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl StagingProfile {
+    pub fn profile_mode(&self) -> ProfileMode {
+        ProfileMode::from(self.mode.clone())
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct Properties {
     #[serde(rename = "@class")]
     pub class: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct StagingActivity {
     pub name: String,
     pub started: String,
@@ -131,8 +327,18 @@ pub struct StagingActivity {
     pub events: Vec<StagingActivityEvent>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
-#[serde(rename_all = "camelCase")]
+impl StagingActivity {
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        datetime_from_rfc3339(&self.started)
+    }
+
+    pub fn stopped_at(&self) -> Option<DateTime<Utc>> {
+        datetime_from_rfc3339(&self.stopped)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct StagingActivityEvent {
     pub timestamp: String,
     pub name: String,
@@ -140,9 +346,32 @@ pub struct StagingActivityEvent {
     pub properties: Vec<StagingProperty>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
-#[serde(rename_all = "camelCase")]
+impl StagingActivityEvent {
+    pub fn timestamp_at(&self) -> Option<DateTime<Utc>> {
+        datetime_from_rfc3339(&self.timestamp)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct StagingProperty {
     pub name: String,
     pub value: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RepositoryState;
+
+    #[test]
+    fn test_string_enum_roundtrip() -> anyhow::Result<()> {
+        let states: Vec<RepositoryState> = serde_json::from_str(r#"["open", "CLOSED", "transitioning"]"#)?;
+        assert_eq!(states, vec![
+            RepositoryState::Open,
+            RepositoryState::Closed,
+            RepositoryState::Other("transitioning".to_string()),
+        ]);
+        assert_eq!(serde_json::to_string(&states)?, r#"["open","closed","transitioning"]"#);
+        Ok(())
+    }
+}
@@ -1,93 +1,582 @@
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
+use futures_util::{stream, StreamExt};
+
+use reqwest::header::CONTENT_LENGTH;
 use reqwest::Method;
-use tokio::spawn;
-use tokio::task::JoinHandle;
 
-use crate::{NexusClient, NexusRepository, RawRequest};
+use crate::{NexusClient, NexusRepository, RawRequest, util};
+use crate::mirror::CONCURRENT_TRANSFERS;
 use crate::model::{DirEntry, NexusResponseData};
+use crate::signing::{is_signable, Signer, SIGNATURE_EXTENSION, UNSIGNED_EXTENSIONS};
+
+/// A file that was uploaded or downloaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferredFile {
+    pub local_path: PathBuf,
+    /// path in the remote repository, starting with slash
+    pub remote_path: String,
+}
+
+/// Names of files and directories which never belong to a remote repository; mostly leftovers of Maven local repository
+pub const DEFAULT_IGNORES: &[&str] = &[".DS_Store", ".git", "_remote.repositories", "*.lastUpdated"];
+/// Per-directory file with patterns of files to skip during upload, similar to `.gitignore`
+pub const IGNORE_FILE: &str = ".nexusignore";
+
+/// Which local files are uploaded by [http_upload]
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// when not empty, only files matching any of these are uploaded; matched against path relative to the uploaded root
+    pub include: Vec<glob::Pattern>,
+    /// files and directories matching any of these are skipped; matched against path relative to the uploaded root
+    pub exclude: Vec<glob::Pattern>,
+    /// skip names listed in [DEFAULT_IGNORES]
+    pub default_ignores: bool,
+    /// honor [IGNORE_FILE] files found in uploaded directories
+    pub ignore_files: bool,
+    /// upload targets of symbolic links; otherwise symbolic links are skipped
+    pub follow_symlinks: bool,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            default_ignores: true,
+            ignore_files: true,
+            follow_symlinks: false,
+        }
+    }
+}
+
+/// Patterns read from one [IGNORE_FILE]
+struct IgnoreFile {
+    dir: PathBuf,
+    patterns: Vec<(glob::Pattern, bool /*dir only*/)>,
+}
 
-/// Full blind upload of a directory
-pub async fn http_upload(nexus: &NexusClient, repository_id: &str, remote_root: &str, root: &Path) -> anyhow::Result<()> {
-    let walker = walkdir::WalkDir::new(root)
-        .sort_by_file_name();
-    let root = root.display().to_string();
-    log::debug!("root: {root}");
-    for entry in walker {
+impl IgnoreFile {
+    fn load(dir: &Path) -> anyhow::Result<Option<Self>> {
+        let path = dir.join(IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let mut patterns = Vec::new();
+        for line in std::fs::read_to_string(&path)?.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (line, dir_only) = match line.strip_suffix('/') {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+            let pattern = glob::Pattern::new(line.trim_start_matches('/'))
+                .map_err(|e| anyhow::anyhow!("{}: invalid pattern '{line}': {e}", path.display()))?;
+            patterns.push((pattern, dir_only));
+        }
+        Ok(Some(Self { dir: dir.to_path_buf(), patterns }))
+    }
+
+    /// Patterns without slash match the name at any depth, others match the path relative to the ignore file
+    fn ignores(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relpath) = path.strip_prefix(&self.dir) else {
+            return false;
+        };
+        let relpath = relpath.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+        let name = relpath.rsplit('/').next().unwrap_or(&relpath);
+        self.patterns.iter()
+            .filter(|(_, dir_only)| is_dir || !dir_only)
+            .any(|(pattern, _)| if pattern.as_str().contains('/') { pattern.matches(&relpath) } else { pattern.matches(name) })
+    }
+}
+
+/// Local files to be uploaded from `root`, in the order of upload
+pub fn upload_files(root: &Path, options: &UploadOptions) -> anyhow::Result<Vec<PathBuf>> {
+    let default_ignores = DEFAULT_IGNORES.iter()
+        .map(|pattern| glob::Pattern::new(pattern))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ignore_files: Vec<IgnoreFile> = Vec::new();
+    let mut files = Vec::new();
+    let mut walker = walkdir::WalkDir::new(root)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
         let entry = entry?;
         let entry_path = entry.path();
-        if entry_path.is_dir() {
+        let is_dir = entry.file_type().is_dir();
+        if entry.depth() > 0 {
+            if entry.path_is_symlink() && !options.follow_symlinks {
+                log::warn!("skipping symbolic link: {}", entry_path.display());
+                continue;
+            }
+            let relpath = entry_path.strip_prefix(root)?.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+            let name = entry.file_name().to_string_lossy();
+            // the walk is depth-first, so ignore files of already left directories are not needed anymore
+            ignore_files.retain(|ignore_file| entry_path.starts_with(&ignore_file.dir));
+            let skipped = (options.default_ignores && default_ignores.iter().any(|pattern| pattern.matches(&name)))
+                || (options.ignore_files && (name == IGNORE_FILE || ignore_files.iter().any(|ignore_file| ignore_file.ignores(entry_path, is_dir))))
+                || options.exclude.iter().any(|pattern| pattern.matches(&relpath));
+            if skipped {
+                log::debug!("skipping ignored: {}", entry_path.display());
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            let included = options.include.is_empty() || options.include.iter().any(|pattern| pattern.matches(&relpath));
+            if !is_dir && !included {
+                log::debug!("skipping not included: {}", entry_path.display());
+                continue;
+            }
+        }
+        if is_dir {
+            if options.ignore_files {
+                ignore_files.extend(IgnoreFile::load(entry_path)?);
+            }
+            continue;
+        }
+        files.push(entry_path.to_path_buf());
+    }
+    Ok(files)
+}
+
+/// Remote path of a `file` found under local `root`, when uploaded to `remote_root`.
+/// Local separators become `/`; components like `..` are rejected, as they would escape the remote root.
+pub(crate) fn upload_path(remote_root: &str, root: &Path, file: &Path) -> anyhow::Result<String> {
+    let relpath = file.strip_prefix(root)
+        .map_err(|_| anyhow::anyhow!("{} is not inside {}", file.display(), root.display()))?;
+    let mut remote_path = format!("/{}", remote_root.trim_matches('/'));
+    for component in relpath.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(name) => {
+                let Some(name) = name.to_str() else {
+                    anyhow::bail!("File name is not valid UTF-8: {}", file.display());
+                };
+                if !remote_path.ends_with('/') {
+                    remote_path.push('/');
+                }
+                remote_path.push_str(name);
+            }
+            _ => anyhow::bail!("Unsupported path component {component:?} in {}", file.display()),
+        }
+    }
+    Ok(remote_path)
+}
+
+/// Full blind upload of a directory, except files skipped by `options`.
+///
+/// With a `signer`, each artifact gets its detached signature uploaded alongside; signatures already present in `root` are skipped then.
+pub async fn http_upload(nexus: &NexusClient, repository_id: &str, remote_root: &str, root: &Path, signer: Option<&Arc<dyn Signer>>, options: &UploadOptions) -> anyhow::Result<Vec<TransferredFile>> {
+    let mut uploaded = Vec::new();
+    let files = upload_files(root, options)?;
+    log::debug!("root: {}", root.display());
+    for entry_path in &files {
+        let signable = is_signable(entry_path);
+        if signer.is_some() && entry_path.extension().is_some_and(|ext| ext == SIGNATURE_EXTENSION) {
+            log::debug!("skipping existing signature: {}", entry_path.display());
             continue;
         }
-        let epd = entry_path.display().to_string();
-        let relpath = &epd[root.len()..];
-        let abspath = format!("{remote_root}{relpath}");
-        println!("* {epd} -> ::/{repository_id}{abspath}");
-        //TODO: abspath must start with slash
+        let abspath = upload_path(remote_root, root, entry_path)?;
+        log::info!("* {} -> ::/{repository_id}{abspath}", entry_path.display());
         nexus.upload_file(repository_id, entry_path, &abspath).await?;
+        if let (Some(signer), true) = (signer, signable) {
+            upload_signature(nexus, repository_id, signer, entry_path, &abspath).await?;
+        }
+        uploaded.push(TransferredFile {
+            local_path: entry_path.to_path_buf(),
+            remote_path: abspath,
+        });
+    }
+    Ok(uploaded)
+}
+
+/// Signs local `file` and uploads the signature next to its remote counterpart at `path`.
+///
+/// Signing runs on the blocking thread pool, as the signer may wait for an external process.
+pub async fn upload_signature(nexus: &NexusClient, repository_id: &str, signer: &Arc<dyn Signer>, file: &Path, path: &str) -> anyhow::Result<()> {
+    let signer = Arc::clone(signer);
+    let file = file.to_path_buf();
+    let signature = tokio::task::spawn_blocking(move || signer.sign(&file)).await??;
+    let signature_path = format!("{path}.{SIGNATURE_EXTENSION}");
+    log::info!("* (signature) -> ::/{repository_id}{signature_path}");
+    nexus.upload_bytes(repository_id, signature, &signature_path).await?;
+    Ok(())
+}
+
+/// A file copied between remote repositories
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopiedFile {
+    /// path in the source repository, starting with slash
+    pub source_path: String,
+    /// path in the target repository, starting with slash
+    pub target_path: String,
+    /// as announced by the server; unknown with chunked transfer
+    pub size: Option<u64>,
+}
+
+/// Copies a remote file into another repository, streaming it without touching local disk.
+/// Checksum and signature files next to the source (like `*.jar.sha1`, `*.jar.asc`) are copied along.
+pub async fn copy_file(nexus: &NexusClient, source_repo: &str, source_path: &str, target_repo: &str, target_path: &str) -> anyhow::Result<Vec<CopiedFile>> {
+    let (source_dir, name) = source_path.rsplit_once('/').unwrap_or(("", source_path));
+    let siblings = fetch_dir(nexus, source_repo, &format!("{source_dir}/")).await?;
+    let mut copied = vec![copy_op(nexus.clone(), source_repo.to_string(), source_path.to_string(), target_repo.to_string(), target_path.to_string()).await?];
+    for sibling in siblings.iter().filter(|entry| entry.leaf) {
+        let Some(suffix) = sibling.text.strip_prefix(name).and_then(|suffix| suffix.strip_prefix('.')) else {
+            continue;
+        };
+        if suffix.split('.').all(|ext| UNSIGNED_EXTENSIONS.contains(&ext)) {
+            let source_path = format!("{source_path}.{suffix}");
+            let target_path = format!("{target_path}.{suffix}");
+            copied.push(copy_op(nexus.clone(), source_repo.to_string(), source_path, target_repo.to_string(), target_path).await?);
+        }
+    }
+    Ok(copied)
+}
+
+/// Copies a remote directory with all its content into another repository; both roots must end with slash.
+pub async fn copy_tree(nexus: &NexusClient, source_repo: &str, source_root: &str, target_repo: &str, target_root: &str) -> anyhow::Result<Vec<CopiedFile>> {
+    let files = list_remote_files(nexus, source_repo, source_root).await?;
+    let results: Vec<anyhow::Result<CopiedFile>> = stream::iter(files.into_keys())
+        .map(|subpath| copy_op(nexus.clone(),
+                               source_repo.to_string(),
+                               format!("{source_root}{subpath}"),
+                               target_repo.to_string(),
+                               format!("{target_root}{subpath}")))
+        .buffer_unordered(CONCURRENT_TRANSFERS)
+        .collect().await;
+    let (mut copied, error) = split_results(results);
+    copied.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+    log::info!("Copied {} files from ::/{source_repo}{source_root} to ::/{target_repo}{target_root}", copied.len());
+    error.map_or(Ok(copied), Err)
+}
+
+/// Separates results of concurrent transfers into the successful ones, and the first error - if any.
+/// All errors are logged, as only the first one is propagated.
+pub(crate) fn split_results<T>(results: Vec<anyhow::Result<T>>) -> (Vec<T>, Option<anyhow::Error>) {
+    let mut succeeded = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => succeeded.push(value),
+            Err(e) => {
+                log::error!("{e}");
+                errors.push(e);
+            }
+        }
+    }
+    if errors.len() > 1 {
+        log::error!("{} errors encountered, proceeding with first", errors.len());
+    }
+    (succeeded, errors.into_iter().next())
+}
+
+async fn copy_op(nexus: NexusClient, source_repo: String, source_path: String, target_repo: String, target_path: String) -> anyhow::Result<CopiedFile> {
+    log::info!("* ::/{source_repo}{source_path} -> ::/{target_repo}{target_path}");
+    let response = nexus.get_file(&source_repo, &source_path, None).await?;
+    let size = response.content_length();
+    nexus.upload_response(&target_repo, &target_path, response).await?;
+    Ok(CopiedFile {
+        source_path,
+        target_path,
+        size,
+    })
+}
+
+/// Checks that the copy has the same SHA-1 as its source, according to the server.
+/// When the server does not provide checksums, sizes are compared; when neither is known on both sides, the copy is not verified.
+pub async fn verify_copy(nexus: &NexusClient, source_repo: &str, target_repo: &str, copied: &CopiedFile) -> anyhow::Result<()> {
+    let source = nexus.execute(NexusRepository::nexus_readonly(source_repo).describe(&copied.source_path)).await?.parsed().await.ok();
+    let target = nexus.execute(NexusRepository::nexus_readonly(target_repo).describe(&copied.target_path)).await?.parsed().await.ok();
+    let (source, target) = match (&source, &target) {
+        (Some(source), Some(target)) if !source.sha1_hash.is_empty() && !target.sha1_hash.is_empty() => {
+            (source.sha1_hash.clone(), target.sha1_hash.clone())
+        }
+        _ => {
+            log::debug!("No checksums available for {}, comparing sizes", copied.target_path);
+            let source_size = copied.size
+                .or_else(|| source.as_ref().and_then(|info| u64::try_from(info.size).ok()));
+            let target_size = match nexus.head_file(target_repo, &copied.target_path).await?
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok()) {
+                Some(size) => Some(size),
+                None => target.as_ref().and_then(|info| u64::try_from(info.size).ok()),
+            };
+            match (source_size, target_size) {
+                (Some(source_size), Some(target_size)) => (source_size.to_string(), target_size.to_string()),
+                _ => anyhow::bail!("Cannot verify copy ::/{target_repo}{}: neither checksum nor size is known on both sides", copied.target_path),
+            }
+        }
+    };
+    if source != target {
+        anyhow::bail!("Copy ::/{target_repo}{} differs from its source ::/{source_repo}{}: '{source}' != '{target}'", copied.target_path, copied.source_path);
     }
     Ok(())
 }
 
-async fn download_op(nexus: NexusClient, repo_id: String, rpath: String, local_path: PathBuf) -> anyhow::Result<()> {
+async fn download_op(nexus: NexusClient, repo_id: String, rpath: String, local_path: PathBuf) -> anyhow::Result<TransferredFile> {
     log::debug!("Downloading {}::{} \t-> {}", repo_id, rpath,  local_path.display());
-    nexus.download_file(&repo_id, &local_path, &rpath).await?;
+    nexus.download_file(&repo_id, &local_path, &rpath, true).await?;
     // http_get_file(client, furl, file).await?;
     log::debug!("downloaded {}", rpath);
-    Ok(())
+    Ok(TransferredFile {
+        local_path,
+        remote_path: rpath,
+    })
 }
 
-pub async fn http_download_tree(nexus: &NexusClient, repo_id: &str, remote_root: &str, local_root: &Path) -> anyhow::Result<()> {
-    let mut handles: Vec<JoinHandle<anyhow::Result<()>>> = Vec::new();
-    let mut subdirs = Vec::new();
-    subdirs.push("".to_string());
-    while let Some(subdir) = subdirs.pop() {
-        let local_path = local_root.join(&subdir);
-        if !local_path.exists() {
-            tokio::fs::create_dir(local_path).await?;
+pub async fn http_download_tree(nexus: &NexusClient, repo_id: &str, remote_root: &str, local_root: &Path) -> anyhow::Result<Vec<TransferredFile>> {
+    tokio::fs::create_dir_all(local_root).await?;
+    let mut downloads = Vec::new();
+    for (subpath, entry) in walk_remote_tree(nexus, repo_id, remote_root).await? {
+        let local_path = local_root.join(&subpath);
+        if entry.leaf {
+            downloads.push((format!("{remote_root}{subpath}"), local_path));
+        } else {
+            // also the empty ones
+            tokio::fs::create_dir_all(local_path).await?;
         }
+    }
+    let results: Vec<anyhow::Result<TransferredFile>> = stream::iter(downloads)
+        .map(|(remote_path, local_path)| download_op(nexus.clone(), repo_id.to_string(), remote_path, local_path))
+        .buffer_unordered(CONCURRENT_TRANSFERS)
+        .collect().await;
+    let (downloaded, error) = split_results(results);
+    log::info!("Downloaded {} files from ::/{repo_id}{remote_root} to {}/", downloaded.len(), local_root.display());
+    error.map_or(Ok(downloaded), Err)
+}
+
+/// Downloads files matching a path pattern (see [glob_remote]); matched directories are downloaded with all their contents.
+/// Local layout is relative to the deepest directory of the pattern without wildcards, or just file names with `flatten`.
+pub async fn http_download_glob(nexus: &NexusClient, repo_id: &str, pattern: &str, local_root: &Path, flatten: bool) -> anyhow::Result<Vec<TransferredFile>> {
+    let base = glob_base(pattern);
+    let mut remote_paths = Vec::new();
+    for entry in glob_remote(nexus, repo_id, pattern).await? {
+        if entry.leaf {
+            remote_paths.push(entry.relative_path);
+        } else {
+            let files = list_remote_files(nexus, repo_id, &entry.relative_path).await?;
+            remote_paths.extend(files.into_keys().map(|subpath| format!("{}{subpath}", entry.relative_path)));
+        }
+    }
+    let mut local_paths: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for remote_path in remote_paths {
+        let subpath = if flatten {
+            remote_path.rsplit('/').next().unwrap_or(&remote_path)
+        } else {
+            remote_path.strip_prefix(base).unwrap_or(&remote_path)
+        };
+        let local_path = local_root.join(subpath);
+        if let Some(other) = local_paths.get(&local_path) {
+            anyhow::bail!("Both {other} and {remote_path} would be downloaded to {}", local_path.display());
+        }
+        local_paths.insert(local_path, remote_path);
+    }
+    let results: Vec<anyhow::Result<TransferredFile>> = stream::iter(local_paths)
+        .map(|(local_path, remote_path)| download_op(nexus.clone(), repo_id.to_string(), remote_path, local_path))
+        .buffer_unordered(CONCURRENT_TRANSFERS)
+        .collect().await;
+    let (downloaded, error) = split_results(results);
+    log::info!("Downloaded {} files matching ::/{repo_id}{pattern} to {}/", downloaded.len(), local_root.display());
+    error.map_or(Ok(downloaded), Err)
+}
+
+/// The directory part of a pattern before its first wildcard, with trailing slash
+fn glob_base(pattern: &str) -> &str {
+    let literal = pattern.find(['*', '?', '[']).map_or(pattern, |index| &pattern[..index]);
+    literal.rfind('/').map_or("", |index| &pattern[..=index])
+}
+
+/// Lists all files and directories of a remote tree, keyed by their path relative to `remote_root` (which must end with slash).
+/// Directories are keyed with trailing slash.
+pub async fn walk_remote_tree(nexus: &NexusClient, repo_id: &str, remote_root: &str) -> anyhow::Result<BTreeMap<String, DirEntry>> {
+    let mut tree = BTreeMap::new();
+    let mut subdirs = vec!["".to_string()];
+    while let Some(subdir) = subdirs.pop() {
         // subdir: either empty, or has trailing slash; never leading slash
-        let remote_dir = format!("{remote_root}{subdir}");
-        let entries = fetch_dir_for_recurse(nexus, repo_id, &remote_dir).await?;
-        for entry in &entries {
+        let entries = fetch_dir_for_recurse(nexus, repo_id, &format!("{remote_root}{subdir}")).await?;
+        for entry in entries {
             if entry.leaf {
-                let subpath = format!("{subdir}{}", entry.text);
-                let local_path = local_root.join(&subpath);
-                let remote_path = format!("{remote_root}{subpath}");
-                handles.push(spawn(download_op(nexus.clone(),
-                                               repo_id.to_string(),
-                                               remote_path,
-                                               local_path)));
+                tree.insert(format!("{subdir}{}", entry.text), entry);
             } else {
                 let subpath = format!("{subdir}{}/", entry.text);
-                subdirs.push(subpath);
+                subdirs.push(subpath.clone());
+                tree.insert(subpath, entry);
             }
         }
     }
-    let mut errors = Vec::new();
-    let mut cnt = 0;
-    for handle in handles {
-        if let Err(e) = handle.await? {
-            log::error!("{e}");
-            errors.push(e);
+    Ok(tree)
+}
+
+/// Lists all files of a remote tree, keyed by their path relative to `remote_root` (which must end with slash).
+pub async fn list_remote_files(nexus: &NexusClient, repo_id: &str, remote_root: &str) -> anyhow::Result<BTreeMap<String, DirEntry>> {
+    let mut files = walk_remote_tree(nexus, repo_id, remote_root).await?;
+    files.retain(|_, entry| entry.leaf);
+    Ok(files)
+}
+
+/// Lists all files of a local tree, keyed by their path relative to `local_root`, with `/` as separator.
+pub fn list_local_files(local_root: &Path) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(local_root) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(local_root)?;
+        let subpath: Vec<String> = relative.components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+        files.insert(subpath.join("/"), entry.into_path());
+    }
+    Ok(files)
+}
+
+/// How a file differs between local and remote tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStatus {
+    OnlyLocal,
+    OnlyRemote,
+    Differs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDifference {
+    /// relative to both roots, with `/` as separator
+    pub path: String,
+    pub status: DiffStatus,
+    pub local_size: Option<u64>,
+    pub remote_size: Option<u64>,
+    /// what differs, for [DiffStatus::Differs]
+    pub detail: Option<String>,
+}
+
+/// Compares local tree with remote one, reporting files which are missing on either side, or differ.
+///
+/// Files present on both sides are compared by size first, then by SHA-1. Remote SHA-1 is read from the `*.sha1` file
+/// next to it when there is one, otherwise the remote file is downloaded and hashed.
+pub async fn diff_tree(nexus: &NexusClient, repo_id: &str, remote_root: &str, local_root: &Path) -> anyhow::Result<Vec<FileDifference>> {
+    let remote_files = list_remote_files(nexus, repo_id, remote_root).await?;
+    let local_files = list_local_files(local_root)?;
+    let mut differences = Vec::new();
+    for (path, local_path) in &local_files {
+        let local_size = std::fs::metadata(local_path)?.len();
+        let Some(remote) = remote_files.get(path) else {
+            differences.push(FileDifference {
+                path: path.clone(),
+                status: DiffStatus::OnlyLocal,
+                local_size: Some(local_size),
+                remote_size: None,
+                detail: None,
+            });
+            continue;
+        };
+        let remote_size = u64::try_from(remote.size_on_disk).ok();
+        let detail = if remote_size != Some(local_size) {
+            Some("size".to_string())
         } else {
-            cnt += 1;
+            let local_sha1 = util::sha1_file(local_path)?;
+            let sidecar = format!("{path}.sha1");
+            let remote_path = format!("{remote_root}{path}");
+            let remote_sha1 = if remote_files.contains_key(&sidecar) {
+                let text = nexus.get_file(repo_id, &format!("{remote_root}{sidecar}"), None).await?.text().await?;
+                // some tools append file name after the hash
+                text.split_whitespace().next().unwrap_or("").to_lowercase()
+            } else {
+                log::debug!("No checksum file for {remote_path}, downloading to compute it");
+                remote_sha1(nexus, repo_id, &remote_path).await?
+            };
+            (local_sha1 != remote_sha1).then(|| "sha1".to_string())
+        };
+        if detail.is_some() {
+            differences.push(FileDifference {
+                path: path.clone(),
+                status: DiffStatus::Differs,
+                local_size: Some(local_size),
+                remote_size,
+                detail,
+            });
         }
     }
-    log::info!("Downloaded {cnt} files from ::/{repo_id}{remote_root} to {}/", local_root.display());
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        log::error!("{} errors encountered, proceeding with first", errors.len());
-        Err(errors.pop().unwrap())
+    for (path, remote) in &remote_files {
+        if !local_files.contains_key(path) {
+            differences.push(FileDifference {
+                path: path.clone(),
+                status: DiffStatus::OnlyRemote,
+                local_size: None,
+                remote_size: u64::try_from(remote.size_on_disk).ok(),
+                detail: None,
+            });
+        }
+    }
+    differences.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(differences)
+}
+
+/// Downloads remote file to compute its SHA-1, without storing it.
+async fn remote_sha1(nexus: &NexusClient, repo_id: &str, path: &str) -> anyhow::Result<String> {
+    let mut stream = nexus.get_file(repo_id, path, None).await?.bytes_stream();
+    let mut hasher = sha1_smol::Sha1::new();
+    while let Some(chunk) = stream.next().await {
+        hasher.update(&chunk?);
+    }
+    Ok(hasher.digest().to_string())
+}
+
+/// Whether the path contains glob pattern characters, to be expanded by [glob_remote]
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Finds remote files and directories matching the `pattern`, which is a path with glob patterns in its segments,
+/// like `/org/foo/*/1.*/*.pom`. Each segment is matched against one directory level, so non-matching subtrees are never listed.
+/// A pattern with trailing slash matches only directories.
+pub async fn glob_remote(nexus: &NexusClient, repo_id: &str, pattern: &str) -> anyhow::Result<Vec<DirEntry>> {
+    let Some(pattern) = pattern.strip_prefix('/') else {
+        anyhow::bail!("Remote path pattern must start with slash: '{pattern}'");
+    };
+    let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let segments: Vec<&str> = pattern.split('/').collect();
+    // segments before the first pattern need no listing
+    let literal = segments.iter().take_while(|segment| !is_glob(segment)).count().min(segments.len() - 1);
+    let mut dirs = vec![format!("/{}", segments[..literal].iter().map(|segment| format!("{segment}/")).collect::<String>())];
+    let mut matched = Vec::new();
+    for (index, segment) in segments.iter().enumerate().skip(literal) {
+        let segment_pattern = glob::Pattern::new(segment)?;
+        let last = index == segments.len() - 1;
+        let listings = futures_util::future::try_join_all(dirs.iter().map(|dir| fetch_dir_for_recurse(nexus, repo_id, dir))).await?;
+        dirs = Vec::new();
+        for entry in listings.into_iter().flatten() {
+            if !segment_pattern.matches(&entry.text) {
+                continue;
+            }
+            if last {
+                if !(dirs_only && entry.leaf) {
+                    matched.push(entry);
+                }
+            } else if !entry.leaf {
+                dirs.push(entry.relative_path);
+            }
+        }
+        log::debug!("'{segment}' matches {} directories", dirs.len());
     }
+    matched.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(matched)
 }
 
 pub async fn fetch_dir(nexus: &NexusClient, repo_id: &str, remote_dir: &str) -> anyhow::Result<Vec<DirEntry>> {
     let request = NexusRepository::nexus_readonly(repo_id)
         .list(remote_dir);
     let response = nexus.execute(request).await?;
-    Ok(response.parsed().await?)
+    response.parsed().await
 }
 
 /// TODO we have to get rid of `dyn` in the NexusRequest, in order to be able to use it instead of RawRequest.
@@ -97,7 +586,7 @@ pub async fn fetch_dir_for_recurse(nexus: &NexusClient, repo_id: &str, remote_di
     let nexus_url_path = NexusRepository::nexus_readonly(repo_id).repo_path;
     let request = RawRequest {
         method: Method::GET,
-        url_suffix: format!("{nexus_url_path}{remote_dir}"),
+        url_suffix: format!("{nexus_url_path}{}", util::encode_path(remote_dir)),
         body: Default::default(),
         content_type: "application/json",
         accept: "application/json",
@@ -108,3 +597,328 @@ pub async fn fetch_dir_for_recurse(nexus: &NexusClient, repo_id: &str, remote_di
     log::trace!("{remote_dir}  END.");
     Ok(dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use crate::signing::Signer;
+    use crate::test_server::{Repositories, repository, Response, TestServer};
+
+    use super::{copy_file, copy_tree, DiffStatus, diff_tree, glob_base, glob_remote, http_download_glob, http_download_tree, is_glob, upload_files, upload_path, split_results, upload_signature, UploadOptions, verify_copy, walk_remote_tree};
+
+    const FILES: [(&str, &str); 4] = [
+        ("org/example/demo/1.0/demo-1.0.pom", "<project/>"),
+        ("org/example/demo/1.0/demo-1.0.jar", "demo 1.0"),
+        ("org/example/demo/1.1/demo-1.1.jar", "demo 1.1"),
+        ("org/example/util/1.0/demo-1.0.jar", "util 1.0"),
+    ];
+
+    struct FakeSigner;
+
+    impl Signer for FakeSigner {
+        fn sign(&self, file: &Path) -> anyhow::Result<Vec<u8>> {
+            Ok(format!("signature of {}", file.display()).into_bytes())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_signature() -> anyhow::Result<()> {
+        let server = TestServer::start(|_| Response::status(201)).await?;
+        let nexus = server.client()?;
+        let signer: Arc<dyn Signer> = Arc::new(FakeSigner);
+        upload_signature(&nexus, "orgexample-1001", &signer, Path::new("demo-1.0.jar"), "/org/example/demo/1.0/demo-1.0.jar").await?;
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/service/local/staging/deployByRepositoryId/orgexample-1001/org/example/demo/1.0/demo-1.0.jar.asc");
+        assert_eq!(requests[0].body, b"signature of demo-1.0.jar");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_signature_failure() -> anyhow::Result<()> {
+        let server = TestServer::start(|_| Response::status(400)).await?;
+        let nexus = server.client()?;
+        let signer: Arc<dyn Signer> = Arc::new(FakeSigner);
+        let result = upload_signature(&nexus, "orgexample-1001", &signer, Path::new("demo-1.0.jar"), "/org/example/demo/1.0/demo-1.0.jar").await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_files_skip_ignored() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("org/example/demo/1.0"))?;
+        std::fs::create_dir_all(root.join("org/example/demo/build"))?;
+        std::fs::write(root.join("org/example/demo/1.0/demo-1.0.pom"), "<project/>")?;
+        std::fs::write(root.join("org/example/demo/1.0/demo-1.0.jar"), "")?;
+        std::fs::write(root.join("org/example/demo/1.0/_remote.repositories"), "")?;
+        std::fs::write(root.join("org/example/demo/1.0/demo-1.0.jar.lastUpdated"), "")?;
+        std::fs::write(root.join("org/example/demo/build/notes.txt"), "")?;
+        std::fs::write(root.join("org/example/.nexusignore"), "# local stuff\nbuild/\n*.jar\n")?;
+        let options = UploadOptions::default();
+        let files = upload_files(root, &options)?;
+        let included = upload_files(root, &UploadOptions {
+            include: vec![glob::Pattern::new("**/*.jar*")?],
+            default_ignores: false,
+            ignore_files: false,
+            ..options
+        })?;
+        assert_eq!(files, vec![root.join("org/example/demo/1.0/demo-1.0.pom")]);
+        assert_eq!(included, vec![
+            root.join("org/example/demo/1.0/demo-1.0.jar"),
+            root.join("org/example/demo/1.0/demo-1.0.jar.lastUpdated"),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_upload_path() -> anyhow::Result<()> {
+        let root = Path::new("./repo/");
+        assert_eq!(upload_path("/releases/", root, &root.join("org/my lib/demo-1.0+build.jar"))?, "/releases/org/my lib/demo-1.0+build.jar");
+        assert_eq!(upload_path("/", Path::new("repo"), Path::new("repo/org/čeština/ünïcode.pom"))?, "/org/čeština/ünïcode.pom");
+        assert_eq!(upload_path("nested", root, &root.join("a.txt"))?, "/nested/a.txt");
+        assert!(upload_path("/", root, &root.join("../escaped.txt")).is_err());
+        assert!(upload_path("/", root, Path::new("elsewhere/a.txt")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_glob() {
+        assert!(is_glob("/org/example/*/1.0/"));
+        assert!(is_glob("/org/example/demo/1.?/"));
+        assert!(is_glob("/org/example/demo/[12].0/"));
+        assert!(!is_glob("/org/example/demo/1.0/demo-1.0.jar"));
+    }
+
+    #[test]
+    fn test_glob_base() {
+        assert_eq!(glob_base("/org/example/*/1.0/*.jar"), "/org/example/");
+        assert_eq!(glob_base("/org/example/demo/1.*"), "/org/example/demo/");
+        assert_eq!(glob_base("/org/example/demo/1.0/demo-1.0.jar"), "/org/example/demo/1.0/");
+        assert_eq!(glob_base("*.jar"), "");
+    }
+
+    #[tokio::test]
+    async fn test_glob_remote() -> anyhow::Result<()> {
+        let server = TestServer::start(repository("releases", &FILES)).await?;
+        let nexus = server.client()?;
+        let paths = |entries: Vec<crate::model::DirEntry>| entries.into_iter().map(|entry| entry.relative_path).collect::<Vec<_>>();
+
+        assert_eq!(paths(glob_remote(&nexus, "releases", "/org/example/*/1.0/*.jar").await?),
+                   vec!["/org/example/demo/1.0/demo-1.0.jar", "/org/example/util/1.0/demo-1.0.jar"]);
+        assert_eq!(paths(glob_remote(&nexus, "releases", "/org/example/demo/*").await?),
+                   vec!["/org/example/demo/1.0/", "/org/example/demo/1.1/"]);
+        // trailing slash matches only directories
+        assert_eq!(paths(glob_remote(&nexus, "releases", "/org/example/demo/1.0/*/").await?), Vec::<String>::new());
+        assert!(glob_remote(&nexus, "releases", "org/example/*").await.is_err());
+        // literal segments are not listed
+        assert!(server.requests().iter().all(|request| !request.path.ends_with("/content/org/")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_glob() -> anyhow::Result<()> {
+        let server = TestServer::start(repository("releases", &FILES)).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+
+        let downloaded = http_download_glob(&nexus, "releases", "/org/example/demo/*", root, false).await?;
+        let jar = std::fs::read_to_string(root.join("1.1/demo-1.1.jar"))?;
+        let collision = http_download_glob(&nexus, "releases", "/org/example/*/1.0/*.jar", &root.join("flat"), true).await;
+        let flat_exists = root.join("flat").exists();
+
+        assert_eq!(downloaded.len(), 3);
+        assert_eq!(jar, "demo 1.1");
+        let message = collision.unwrap_err().to_string();
+        assert!(message.contains("would be downloaded to"), "{message}");
+        assert!(!flat_exists);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_download_tree_partial_failure() -> anyhow::Result<()> {
+        let handler = repository("releases", &FILES);
+        let server = TestServer::start(move |request| {
+            if request.path.ends_with("/demo-1.1.jar") {
+                Response::status(500)
+            } else {
+                handler(request)
+            }
+        }).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+
+        let result = http_download_tree(&nexus, "releases", "/org/example/", root).await;
+        let jar = std::fs::read_to_string(root.join("demo/1.0/demo-1.0.jar"))?;
+        let failed_exists = root.join("demo/1.1/demo-1.1.jar").exists();
+
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(message.contains("500"), "{message}");
+        assert_eq!(jar, "demo 1.0");
+        assert!(!failed_exists);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_results() {
+        let results = vec![Ok(1), Err(anyhow::anyhow!("first")), Ok(2), Err(anyhow::anyhow!("second"))];
+        let (succeeded, error) = split_results(results);
+        assert_eq!(succeeded, vec![1, 2]);
+        assert_eq!(error.map(|e| e.to_string()).as_deref(), Some("first"));
+    }
+
+    #[tokio::test]
+    async fn test_copy_file() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &[
+            ("org/example/demo/1.0/demo-1.0.jar", "jar"),
+            ("org/example/demo/1.0/demo-1.0.jar.sha1", "jar sha1"),
+            ("org/example/demo/1.0/demo-1.0.jar.asc", "jar signature"),
+            ("org/example/demo/1.0/demo-1.0.pom", "pom"),
+        ]);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+
+        let copied = copy_file(&nexus, "candidates", "/org/example/demo/1.0/demo-1.0.jar", "releases", "/org/example/demo/1.0/demo-1.0.jar").await?;
+
+        let targets: Vec<&str> = copied.iter().map(|file| file.target_path.as_str()).collect();
+        assert_eq!(targets, vec![
+            "/org/example/demo/1.0/demo-1.0.jar",
+            "/org/example/demo/1.0/demo-1.0.jar.asc",
+            "/org/example/demo/1.0/demo-1.0.jar.sha1",
+        ]);
+        assert_eq!(copied[0].size, Some(3));
+        assert_eq!(repositories.paths("releases"), vec![
+            "org/", "org/example/", "org/example/demo/", "org/example/demo/1.0/",
+            "org/example/demo/1.0/demo-1.0.jar",
+            "org/example/demo/1.0/demo-1.0.jar.asc",
+            "org/example/demo/1.0/demo-1.0.jar.sha1",
+        ]);
+        assert_eq!(repositories.content("releases", "org/example/demo/1.0/demo-1.0.jar.asc").as_deref(), Some("jar signature"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_tree() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &FILES);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+
+        let copied = copy_tree(&nexus, "candidates", "/org/example/demo/", "releases", "/com/example/demo/").await?;
+
+        let sources: Vec<&str> = copied.iter().map(|file| file.source_path.as_str()).collect();
+        assert_eq!(sources, vec![
+            "/org/example/demo/1.0/demo-1.0.jar",
+            "/org/example/demo/1.0/demo-1.0.pom",
+            "/org/example/demo/1.1/demo-1.1.jar",
+        ]);
+        assert_eq!(repositories.content("releases", "com/example/demo/1.1/demo-1.1.jar").as_deref(), Some("demo 1.1"));
+        assert_eq!(repositories.paths("releases").iter().filter(|path| !path.ends_with('/')).count(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_copy() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &FILES);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+        let copied = copy_file(&nexus, "candidates", "/org/example/demo/1.0/demo-1.0.jar", "releases", "/demo.jar").await?;
+
+        let verified = verify_copy(&nexus, "candidates", "releases", &copied[0]).await;
+        // same size, different content
+        repositories.put("releases", "demo.jar", b"demo 1.X");
+        let differs = verify_copy(&nexus, "candidates", "releases", &copied[0]).await;
+
+        verified?;
+        let message = differs.unwrap_err().to_string();
+        assert!(message.contains("differs from its source"), "{message}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_copy_by_size() -> anyhow::Result<()> {
+        let repositories = Repositories::default().with("candidates", &FILES).without_checksums();
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+        let copied = copy_file(&nexus, "candidates", "/org/example/demo/1.0/demo-1.0.jar", "releases", "/demo.jar").await?;
+
+        let verified = verify_copy(&nexus, "candidates", "releases", &copied[0]).await;
+        // without checksums, same size is all that can be checked
+        repositories.put("releases", "demo.jar", b"demo 1.X");
+        let same_size = verify_copy(&nexus, "candidates", "releases", &copied[0]).await;
+        repositories.put("releases", "demo.jar", b"truncated");
+        let differs = verify_copy(&nexus, "candidates", "releases", &copied[0]).await;
+
+        verified?;
+        same_size?;
+        let message = differs.unwrap_err().to_string();
+        assert!(message.contains("'8' != '9'"), "{message}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_walk_special_characters() -> anyhow::Result<()> {
+        let server = TestServer::start(repository("releases", &[("org/a#b?c%d+e f/demo-1.0.jar", "demo")])).await?;
+        let nexus = server.client()?;
+        let tree = walk_remote_tree(&nexus, "releases", "/org/").await?;
+        assert_eq!(tree.keys().collect::<Vec<_>>(), vec!["a#b?c%d+e f/", "a#b?c%d+e f/demo-1.0.jar"]);
+        assert!(server.requests().iter().any(|request| request.path.ends_with("/org/a%23b%3Fc%25d%2Be%20f/")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_diff_tree() -> anyhow::Result<()> {
+        let sha1 = |content: &str| sha1_smol::Sha1::from(content).digest().to_string();
+        let sidecar = format!("{}  sidecar.txt", sha1("stale!"));
+        let repositories = Repositories::default().with("releases", &[
+            ("org/same.txt", "same"),
+            ("org/only-remote.txt", "remote"),
+            ("org/size.txt", "remote"),
+            ("org/sidecar.txt", "remote"),
+            ("org/sidecar.txt.sha1", &sidecar),
+            ("org/downloaded.txt", "remot3"),
+        ]);
+        let server = TestServer::start(repositories.handler()).await?;
+        let nexus = server.client()?;
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        for (path, content) in [
+            ("same.txt", "same"),
+            ("only-local.txt", "local"),
+            ("size.txt", "local"),
+            ("sidecar.txt", "remote"),
+            ("sidecar.txt.sha1", &sidecar),
+            ("downloaded.txt", "remote"),
+        ] {
+            std::fs::write(root.join(path), content)?;
+        }
+
+        let differences = diff_tree(&nexus, "releases", "/org/", root).await;
+
+        let differences: Vec<(String, DiffStatus, Option<String>)> = differences?.into_iter()
+            .map(|difference| (difference.path, difference.status, difference.detail))
+            .collect();
+        let expected = [
+            ("downloaded.txt", DiffStatus::Differs, Some("sha1")),
+            ("only-local.txt", DiffStatus::OnlyLocal, None),
+            ("only-remote.txt", DiffStatus::OnlyRemote, None),
+            // same content, but the checksum file is trusted without downloading
+            ("sidecar.txt", DiffStatus::Differs, Some("sha1")),
+            ("size.txt", DiffStatus::Differs, Some("size")),
+        ].map(|(path, status, detail)| (path.to_string(), status, detail.map(str::to_string)));
+        assert_eq!(differences, expected);
+        let downloaded: Vec<String> = server.requests().into_iter()
+            .filter(|request| request.method == "GET" && request.path.ends_with(".txt"))
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(downloaded.len(), 2, "{downloaded:?}");
+        assert!(downloaded.iter().any(|path| path.ends_with("/org/downloaded.txt")));
+        assert!(downloaded.iter().any(|path| path.ends_with("/org/same.txt")));
+        Ok(())
+    }
+}
@@ -5,13 +5,14 @@ use serde::de::DeserializeOwned;
 use crate::client::NexusRequest;
 use crate::model;
 use crate::model::{NexusResponseData, PromoteResponse, StagingActivity, StagingProfile, StagingProfileRepository};
+use crate::util;
 
 pub const APPLICATION_JSON: &str = "application/json";
 pub const APPLICATION_XML: &str = "application/xml";
 
 /// Extracts content carried inside JSON "data" wrapping object
 fn json_extract_data<A: DeserializeOwned>(text: &str) -> Result<A, Error> {
-    let resp: NexusResponseData = serde_json::from_str(&text)?;
+    let resp: NexusResponseData = serde_json::from_str(text)?;
     Ok(serde_json::from_value(resp.data)?)
 }
 
@@ -35,6 +36,21 @@ impl StagingProfiles {
         )
     }
 
+    /// Find profiles matching given maven coordinates; empty `artifact_id` or `version` match any.
+    pub fn evaluate(group_id: &str, artifact_id: &str, version: &str) -> NexusRequest<Vec<StagingProfile>> {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("t", "maven2")
+            .append_pair("g", group_id)
+            .append_pair("a", artifact_id)
+            .append_pair("v", version)
+            .finish();
+        NexusRequest::json_json(Method::GET,
+                                format!("/service/local/staging/profile_evaluate?{query}"),
+                                "".to_string(),
+                                json_extract_data,
+        )
+    }
+
     pub fn start(profile_id_key: &str, description: &str) -> NexusRequest<PromoteResponse> {
         let body = model::PromoteRequest {
             data: model::PromoteRequestData {
@@ -52,25 +68,6 @@ impl StagingProfiles {
     }
 
 
-    /// NOT WORKING - set repository's description.
-    /// It was reverse-engineered from OSSRH but it doesn't work even there.
-    /// Let's update once the real one is found.
-    pub fn describe(profile_id_key: &str, repository_id: &str, description: &str) -> NexusRequest<String> {
-        // request body is too trivial to bother with JSON - TODO perhaps just fail on strange chars to prevent JSON injection
-        let json_expr_description = if description.is_empty() {
-            "".to_string()
-        } else {
-            format!(r##", "description":"{}""##, json_escape(description))
-        };
-        let json_body = format!(r##"{{"data": {{"repositoryId":"{repository_id}"{json_expr_description}}}}}"##);
-        // response body is empty in OK case, otherwise we don't even get to parse it here
-        NexusRequest::json_json(Method::PUT,
-                                format!("/service/local/staging/profiles/{profile_id_key}"),
-                                json_body,
-                                |text| Ok(text.to_string()),
-        )
-    }
-
     pub fn finish(profile_id_key: &str, repository_id: &str, description: &str) -> NexusRequest<String> {
         // request body is too trivial to bother with JSON - TODO perhaps just fail on strange chars to prevent JSON injection
         let json_expr_description = if description.is_empty() {
@@ -88,8 +85,19 @@ impl StagingProfiles {
     }
 
     pub fn promote(profile_id_key: &str, repository_id: &str) -> NexusRequest<String> {
-        // request body is too trivial to bother with JSON - TODO perhaps just fail on strange chars to prevent JSON injection
-        let json_body = format!(r##"{{"data": {{"stagedRepositoryId":"{repository_id}"}} }}"##);
+        Self::promote_with_description(profile_id_key, repository_id, "")
+    }
+
+    /// Like [Self::promote], recording the `description` with the transition; empty description is not sent.
+    pub fn promote_with_description(profile_id_key: &str, repository_id: &str, description: &str) -> NexusRequest<String> {
+        let body = model::PromoteRequest {
+            data: model::PromoteRequestData {
+                staged_repository_id: Some(repository_id.to_string()),
+                description: (!description.is_empty()).then(|| description.to_string()),
+                target_repository_id: None,
+            }
+        };
+        let json_body = serde_json::to_string(&body).unwrap();
         // response body is empty in OK case, otherwise we don't even get to parse it here
         NexusRequest::json_json(Method::POST,
                                 format!("/service/local/staging/profiles/{profile_id_key}/promote"),
@@ -125,6 +133,14 @@ impl StagingRepositories {
         )
     }
 
+    pub fn list_xml() -> NexusRequest<String> {
+        NexusRequest::xml_xml(Method::GET,
+                              "/service/local/staging/profile_repositories".to_string(),
+                              "".to_string(),
+                              |text| Ok(text.to_string()),
+        )
+    }
+
     pub fn get(staged_repository_id: &str) -> NexusRequest<StagingProfileRepository> {
         NexusRequest::json_json(Method::GET,
                                 format!("/service/local/staging/repository/{staged_repository_id}"),
@@ -141,6 +157,37 @@ impl StagingRepositories {
         )
     }
 
+    /// Finish (close) multiple staging repositories at once, regardless of their profiles.
+    pub fn bulk_close(staged_repository_ids: &[String], description: &str) -> NexusRequest<()> {
+        Self::bulk("close", staged_repository_ids, description)
+    }
+
+    /// Promote (release) multiple staging repositories at once.
+    pub fn bulk_promote(staged_repository_ids: &[String], description: &str) -> NexusRequest<()> {
+        Self::bulk("promote", staged_repository_ids, description)
+    }
+
+    /// Drop multiple staging repositories at once.
+    pub fn bulk_drop(staged_repository_ids: &[String], description: &str) -> NexusRequest<()> {
+        Self::bulk("drop", staged_repository_ids, description)
+    }
+
+    fn bulk(operation: &str, staged_repository_ids: &[String], description: &str) -> NexusRequest<()> {
+        let body = model::StagingBulkRequest {
+            data: model::StagingBulkRequestData {
+                staged_repository_ids: staged_repository_ids.to_vec(),
+                description: (!description.is_empty()).then(|| description.to_string()),
+            }
+        };
+        let json_body = serde_json::to_string(&body).unwrap();
+        // response body is empty in OK case, otherwise we don't even get to parse it here
+        NexusRequest::json_json(Method::POST,
+                                format!("/service/local/staging/bulk/{operation}"),
+                                json_body,
+                                |_| Ok(()),
+        )
+    }
+
     //TODO more elegant solution would be great here
     pub fn activity_xml(staged_repository_id: &str) -> NexusRequest<String> {
         NexusRequest::xml_xml(Method::GET,
@@ -159,8 +206,7 @@ const STAGING_PREFIX: &str = "@staging:";
 
 impl NexusRepository {
     pub fn nexus_readwrite(repository_id: &str) -> Self {
-        let repo_path = if repository_id.starts_with(STAGING_PREFIX) {
-            let repository_id = &repository_id[STAGING_PREFIX.len()..];
+        let repo_path = if let Some(repository_id) = repository_id.strip_prefix(STAGING_PREFIX) {
             format!("/service/local/staging/deployByRepositoryId/{repository_id}")
         } else {
             format!("/service/local/repositories/{repository_id}/content")
@@ -169,11 +215,7 @@ impl NexusRepository {
     }
 
     pub fn nexus_readonly(repository_id: &str) -> Self {
-        let repository_id = if repository_id.starts_with(STAGING_PREFIX) {
-            &repository_id[STAGING_PREFIX.len()..]
-        } else {
-            repository_id
-        };
+        let repository_id = repository_id.strip_prefix(STAGING_PREFIX).unwrap_or(repository_id);
         let repo_path = format!("/service/local/repositories/{repository_id}/content");
         Self { repo_path }
     }
@@ -181,7 +223,7 @@ impl NexusRepository {
     pub fn delete(&self, path: &str) -> NexusRequest<()> {
         NexusRequest {
             method: Method::DELETE,
-            url_suffix: format!("{}{path}", self.repo_path),
+            url_suffix: format!("{}{}", self.repo_path, util::encode_path(path)),
             body: "".to_string(),
             content_type: "",
             accept: "",
@@ -189,10 +231,20 @@ impl NexusRepository {
         }
     }
 
+    /// Metadata of a file, including its checksums.
+    pub fn describe(&self, path: &str) -> NexusRequest<model::StorageItemInfo> {
+        NexusRequest::json_json(
+            Method::GET,
+            format!("{}{}?describe=info", self.repo_path, util::encode_path(path)),
+            "".to_string(),
+            json_extract_data,
+        )
+    }
+
     pub fn list(&self, path: &str) -> NexusRequest<Vec<model::DirEntry>> {
         NexusRequest::json_json(
             Method::GET,
-            format!("{}{path}", self.repo_path),
+            format!("{}{}", self.repo_path, util::encode_path(path)),
             "".to_string(),
             json_extract_data,
         )
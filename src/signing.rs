@@ -0,0 +1,100 @@
+//! Detached ASCII-armored signatures (`*.asc`), as required by Maven Central.
+//!
+//! Only signing with external `gpg` is built in; an in-process OpenPGP signer using a key file is not provided,
+//! to keep an OpenPGP implementation out of the dependencies. It can be added as another [Signer].
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Extension of the detached signature files
+pub const SIGNATURE_EXTENSION: &str = "asc";
+
+/// Files which are never signed themselves - signatures and checksums.
+pub(crate) const UNSIGNED_EXTENSIONS: [&str; 5] = ["asc", "md5", "sha1", "sha256", "sha512"];
+
+/// Produces a detached ASCII-armored signature for a file.
+///
+/// Implemented by [GpgSigner]; other implementations (like an in-process OpenPGP one) can be plugged into
+/// [crate::http_upload] the same way.
+pub trait Signer: Send + Sync {
+    fn sign(&self, file: &Path) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Tells whether the file deserves a signature, i.e. it is not a signature or checksum itself.
+pub fn is_signable(path: &Path) -> bool {
+    match path.extension() {
+        None => true,
+        Some(ext) => !UNSIGNED_EXTENSIONS.iter().any(|unsigned| ext == *unsigned),
+    }
+}
+
+/// Signs by calling external `gpg` binary.
+pub struct GpgSigner {
+    /// the gpg executable; `gpg` from `PATH` by default
+    pub gpg_binary: String,
+    /// key to sign with (`--local-user`); gpg's default key when missing
+    pub key_id: Option<String>,
+    /// passphrase fed to gpg in loopback pinentry mode; gpg-agent is used when missing
+    pub passphrase: Option<String>,
+}
+
+impl GpgSigner {
+    /// Configures the signer from variables `NEXUS_GPG`, `NEXUS_GPG_KEY` and `NEXUS_GPG_PASSPHRASE`.
+    pub fn from_env() -> Self {
+        Self {
+            gpg_binary: std::env::var("NEXUS_GPG").unwrap_or_else(|_| "gpg".to_string()),
+            key_id: std::env::var("NEXUS_GPG_KEY").ok(),
+            passphrase: std::env::var("NEXUS_GPG_PASSPHRASE").ok(),
+        }
+    }
+}
+
+impl Signer for GpgSigner {
+    fn sign(&self, file: &Path) -> anyhow::Result<Vec<u8>> {
+        let mut command = Command::new(&self.gpg_binary);
+        command.args(["--batch", "--yes", "--armor", "--detach-sign", "--output", "-"]);
+        if let Some(key_id) = &self.key_id {
+            command.args(["--local-user", key_id]);
+        }
+        if self.passphrase.is_some() {
+            command.args(["--pinentry-mode", "loopback", "--passphrase-fd", "0"]);
+        }
+        command.arg(file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        log::debug!("signing: {command:?}");
+        let mut child = command.spawn()
+            .map_err(|e| anyhow::anyhow!("Cannot run '{}': {e}", self.gpg_binary))?;
+        {
+            // dropping stdin closes it, so gpg does not wait for more
+            let mut stdin = child.stdin.take().unwrap();
+            if let Some(passphrase) = &self.passphrase {
+                writeln!(stdin, "{passphrase}")?;
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!("Signing {} failed with {}: {}", file.display(), output.status, String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::is_signable;
+
+    #[test]
+    fn test_is_signable() {
+        assert!(is_signable(Path::new("demo-1.0.jar")));
+        assert!(is_signable(Path::new("demo-1.0.pom")));
+        assert!(is_signable(Path::new("LICENSE")));
+        assert!(!is_signable(Path::new("demo-1.0.jar.asc")));
+        assert!(!is_signable(Path::new("demo-1.0.jar.sha1")));
+        assert!(!is_signable(Path::new("demo-1.0.pom.md5")));
+        assert!(!is_signable(Path::new("demo-1.0.pom.sha512")));
+    }
+}
@@ -0,0 +1,245 @@
+//! Minimal HTTP server for tests; every connection carries one request, answered by the given handler.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use url::Url;
+
+use crate::NexusClient;
+
+/// A request received by [TestServer]
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// with lowercase names
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// A response sent by [TestServer]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(body: &[u8]) -> Self {
+        Self { status: 200, headers: Vec::new(), body: body.to_vec() }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self { status, headers: Vec::new(), body: Vec::new() }
+    }
+
+    pub fn header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+pub struct TestServer {
+    pub url: Url,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    pub async fn start<F>(handler: F) -> anyhow::Result<Self>
+        where F: Fn(&Request) -> Response + Send + Sync + 'static
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let Ok(request) = read_request(BufReader::new(reader)).await else { return };
+                    let response = handler(&request);
+                    received.lock().unwrap().push(request);
+                    let mut head = format!("HTTP/1.1 {} Stub\r\nConnection: close\r\n", response.status);
+                    if !response.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-length")) {
+                        head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+                    }
+                    for (name, value) in &response.headers {
+                        head.push_str(&format!("{name}: {value}\r\n"));
+                    }
+                    head.push_str("\r\n");
+                    let _ = writer.write_all(head.as_bytes()).await;
+                    let _ = writer.write_all(&response.body).await;
+                    let _ = writer.shutdown().await;
+                });
+            }
+        });
+        Ok(Self { url, requests })
+    }
+
+    pub fn client(&self) -> anyhow::Result<NexusClient> {
+        NexusClient::anonymous(self.url.clone())
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request<R: AsyncBufReadExt + Unpin>(mut reader: R) -> anyhow::Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await?;
+        match line.trim_end().split_once(':') {
+            Some((name, value)) => headers.insert(name.to_lowercase(), value.trim().to_string()),
+            None => break,
+        };
+    }
+    let length = headers.get("content-length").map_or(Ok(0), |length| length.parse())?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Request { method, path, headers, body })
+}
+
+/// Serves content of a repository holding `files` (paths without leading slash), including directory listings.
+pub fn repository(repo_id: &str, files: &[(&str, &str)]) -> impl Fn(&Request) -> Response + Send + Sync + 'static {
+    Repositories::default().with(repo_id, files).handler()
+}
+
+/// Content of fake hosted repositories, changed by uploads and removals, so that the test can check it afterwards.
+/// Directories stay when their files are removed, like in Nexus.
+#[derive(Clone)]
+pub struct Repositories {
+    /// keyed by `{repo_id}/{path}`; directories have trailing slash and no content
+    entries: Arc<Mutex<BTreeMap<String, Option<Vec<u8>>>>>,
+    /// whether file metadata (`?describe=info`) includes SHA-1
+    checksums: bool,
+}
+
+impl Default for Repositories {
+    fn default() -> Self {
+        Self { entries: Default::default(), checksums: true }
+    }
+}
+
+impl Repositories {
+    /// Adds a repository holding `files` (paths without leading slash).
+    pub fn with(self, repo_id: &str, files: &[(&str, &str)]) -> Self {
+        for (path, content) in files {
+            self.put(repo_id, path, content.as_bytes());
+        }
+        self
+    }
+
+    /// Leaves SHA-1 out of file metadata, like servers which do not compute checksums.
+    pub fn without_checksums(self) -> Self {
+        Self { checksums: false, ..self }
+    }
+
+    /// Stores a file, creating its parent directories.
+    pub fn put(&self, repo_id: &str, path: &str, content: &[u8]) {
+        let mut entries = self.entries.lock().unwrap();
+        for (index, _) in path.match_indices('/') {
+            entries.insert(format!("{repo_id}/{}", &path[..=index]), None);
+        }
+        entries.insert(format!("{repo_id}/{path}"), Some(content.to_vec()));
+    }
+
+    /// Paths of all files and directories in the repository, without leading slash; directories with trailing slash.
+    pub fn paths(&self, repo_id: &str) -> Vec<String> {
+        let prefix = format!("{repo_id}/");
+        self.entries.lock().unwrap().keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Content of a file, as text.
+    pub fn content(&self, repo_id: &str, path: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        let content = entries.get(&format!("{repo_id}/{path}"))?.as_ref()?;
+        Some(String::from_utf8_lossy(content).to_string())
+    }
+
+    /// Handles content requests of [crate::NexusRepository]: reading, listing, describing, uploading and removing.
+    pub fn handler(&self) -> impl Fn(&Request) -> Response + Send + Sync + 'static {
+        let repositories = self.clone();
+        move |request| repositories.handle(request)
+    }
+
+    fn handle(&self, request: &Request) -> Response {
+        let Some(rest) = request.path.strip_prefix("/service/local/repositories/") else {
+            return Response::status(404);
+        };
+        let Some((repo_id, path)) = rest.split_once("/content/") else {
+            return Response::status(404);
+        };
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy().to_string();
+        let key = format!("{repo_id}/{path}");
+        let mut entries = self.entries.lock().unwrap();
+        match request.method.as_str() {
+            "PUT" => {
+                drop(entries);
+                self.put(repo_id, &path, &request.body);
+                Response::status(201)
+            }
+            "DELETE" => {
+                // a directory is removed with its content, with or without trailing slash
+                let dir = format!("{}/", key.trim_end_matches('/'));
+                let count = entries.len();
+                entries.retain(|entry, _| entry != &key && !entry.starts_with(&dir));
+                if entries.len() == count { Response::status(404) } else { Response::status(204) }
+            }
+            "GET" | "HEAD" => match entries.get(&key) {
+                Some(Some(content)) if query == "describe=info" => {
+                    let mut info = serde_json::json!({
+                        "repositoryId": repo_id,
+                        "repositoryPath": format!("/{path}"),
+                        "size": content.len(),
+                    });
+                    if self.checksums {
+                        info["sha1Hash"] = sha1_smol::Sha1::from(content).digest().to_string().into();
+                    }
+                    Response::ok(serde_json::json!({"data": info}).to_string().as_bytes())
+                        .header("Content-Type", "application/json")
+                }
+                Some(Some(content)) if request.method == "HEAD" => Response::status(200)
+                    .header("Content-Length", &content.len().to_string()),
+                Some(Some(content)) => Response::ok(content),
+                _ if !path.is_empty() && !path.ends_with('/') => Response::status(404),
+                _ => {
+                    let children: Vec<(&str, &Option<Vec<u8>>)> = entries.iter()
+                        .filter_map(|(entry, content)| Some((entry.strip_prefix(&key)?, content)))
+                        .filter(|(name, _)| !name.is_empty() && !name.trim_end_matches('/').contains('/'))
+                        .collect();
+                    if children.is_empty() && !entries.contains_key(&key) {
+                        return Response::status(404);
+                    }
+                    let data: Vec<serde_json::Value> = children.iter()
+                        .map(|(name, content)| serde_json::json!({
+                            "resourceURI": format!("{}{name}", request.path),
+                            "relativePath": format!("/{path}{name}"),
+                            "text": name.trim_end_matches('/'),
+                            "leaf": content.is_some(),
+                            "lastModified": "2026-01-01 10:00:00.0 UTC",
+                            "sizeOnDisk": content.as_ref().map_or(-1, |content| content.len() as i64),
+                        }))
+                        .collect();
+                    Response::ok(serde_json::json!({"data": data}).to_string().as_bytes())
+                        .header("Content-Type", "application/json")
+                }
+            },
+            _ => Response::status(405),
+        }
+    }
+}
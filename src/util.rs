@@ -1,5 +1,11 @@
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use reqwest::header::{AUTHORIZATION, HeaderValue};
 
+/// Characters escaped in remote paths; `+` included, because some servers decode it as space
+const PATH_ESCAPED: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'+').add(b'<').add(b'>').add(b'?')
+    .add(b'[').add(b'\\').add(b']').add(b'^').add(b'`').add(b'{').add(b'|').add(b'}');
+
 /// Computes basic auth.
 ///
 /// Unfortunately, function [reqwest::util::basic_auth] is not public in the library, so we call it via its calling code.
@@ -18,3 +24,36 @@ pub fn basic_auth<U, P>(username: U, password: Option<P>) -> HeaderValue
         .unwrap()
         .clone()
 }
+
+/// Hex-encoded SHA-1 of a local file, as found in `*.sha1` files.
+pub fn sha1_file(path: &std::path::Path) -> anyhow::Result<String> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = sha1_smol::Sha1::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let count = file.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(&buffer[..count]);
+    }
+    Ok(hasher.digest().to_string())
+}
+
+/// Percent-encodes a remote path (like `/org/example/my lib/`) for use in URL; slashes are kept.
+pub fn encode_path(path: &str) -> String {
+    utf8_percent_encode(path, PATH_ESCAPED).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_path;
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("/org/my lib/demo-1.0+build.jar"), "/org/my%20lib/demo-1.0%2Bbuild.jar");
+        assert_eq!(encode_path("/org/čeština/a#b?c%d/"), "/org/%C4%8De%C5%A1tina/a%23b%3Fc%25d/");
+        assert_eq!(encode_path("/org/example/demo/1.0/"), "/org/example/demo/1.0/");
+    }
+}
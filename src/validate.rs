@@ -0,0 +1,245 @@
+//! Pre-flight check of a local Maven-layout tree against the Maven Central requirements,
+//! so that problems are found before the staging repository is closed.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+
+use crate::signing::SIGNATURE_EXTENSION;
+
+/// Categories matching the rules of Nexus staging ruleset for Maven Central
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleCategory {
+    Pom,
+    Sources,
+    Javadoc,
+    Signature,
+    Checksum,
+}
+
+impl Display for RuleCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RuleCategory::Pom => "POM Validation",
+            RuleCategory::Sources => "Sources Validation",
+            RuleCategory::Javadoc => "Javadoc Validation",
+            RuleCategory::Signature => "Signature Validation",
+            RuleCategory::Checksum => "Checksum Validation",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Gav {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+}
+
+impl Display for Gav {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.version)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValidationProblem {
+    pub gav: Gav,
+    pub category: RuleCategory,
+    pub message: String,
+}
+
+/// Only the POM elements that are subject of validation
+#[derive(Default, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Pom {
+    packaging: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    licenses: Option<PomLicenses>,
+    developers: Option<PomDevelopers>,
+    scm: Option<PomScm>,
+}
+
+/// Only the count of licenses matters here
+#[derive(Default, Debug, Deserialize)]
+struct PomLicenses {
+    #[serde(default)]
+    license: Vec<IgnoredAny>,
+}
+
+#[derive(Default, Debug, Deserialize)]
+struct PomDevelopers {
+    #[serde(default)]
+    developer: Vec<IgnoredAny>,
+}
+
+#[derive(Default, Debug, Deserialize)]
+struct PomScm {
+    url: Option<String>,
+}
+
+const CHECKSUM_EXTENSIONS: [&str; 2] = ["md5", "sha1"];
+
+/// Validates every GAV found under `root`, which must have Maven repository layout.
+pub fn validate_bundle(root: &Path) -> anyhow::Result<Vec<ValidationProblem>> {
+    let mut version_dirs: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(root).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+            version_dirs.entry(dir.to_path_buf())
+                .or_default()
+                .push(name.to_string_lossy().to_string());
+        }
+    }
+    let mut problems = Vec::new();
+    for (dir, files) in &version_dirs {
+        if let Some(gav) = gav_of_dir(root, dir, files) {
+            validate_gav(dir, &gav, files, &mut problems)?;
+        }
+    }
+    Ok(problems)
+}
+
+/// Recognizes the directory as `<groupId path>/<artifactId>/<version>/`, containing `<artifactId>-<version>.pom`.
+fn gav_of_dir(root: &Path, dir: &Path, files: &[String]) -> Option<Gav> {
+    let relative = dir.strip_prefix(root).ok()?;
+    let components: Vec<String> = relative.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if components.len() < 3 {
+        return None;
+    }
+    let version = &components[components.len() - 1];
+    let artifact_id = &components[components.len() - 2];
+    let pom_name = format!("{artifact_id}-{version}.pom");
+    if !files.contains(&pom_name) {
+        return None;
+    }
+    Some(Gav {
+        group_id: components[..components.len() - 2].join("."),
+        artifact_id: artifact_id.clone(),
+        version: version.clone(),
+    })
+}
+
+fn validate_gav(dir: &Path, gav: &Gav, files: &[String], problems: &mut Vec<ValidationProblem>) -> anyhow::Result<()> {
+    let mut problem = |category: RuleCategory, message: String| {
+        problems.push(ValidationProblem {
+            gav: gav.clone(),
+            category,
+            message,
+        })
+    };
+    let base_name = format!("{}-{}", gav.artifact_id, gav.version);
+    let pom_name = format!("{base_name}.pom");
+    let pom = std::fs::read_to_string(dir.join(&pom_name))?;
+    let pom: Pom = match serde_xml_rs::from_str(&pom) {
+        Ok(pom) => pom,
+        Err(e) => {
+            problem(RuleCategory::Pom, format!("Invalid POM {pom_name}: {e}"));
+            Pom::default()
+        }
+    };
+    for (element, missing) in [
+        ("name", is_blank(&pom.name)),
+        ("description", is_blank(&pom.description)),
+        ("url", is_blank(&pom.url)),
+        ("licenses", pom.licenses.is_none_or(|list| list.license.is_empty())),
+        ("developers", pom.developers.is_none_or(|list| list.developer.is_empty())),
+        ("scm", pom.scm.is_none_or(|scm| is_blank(&scm.url))),
+    ] {
+        if missing {
+            problem(RuleCategory::Pom, format!("Missing <{element}> in {pom_name}"));
+        }
+    }
+
+    if pom.packaging.as_deref().unwrap_or("jar") != "pom" {
+        for (classifier, category) in [("sources", RuleCategory::Sources), ("javadoc", RuleCategory::Javadoc)] {
+            let jar_name = format!("{base_name}-{classifier}.jar");
+            if !files.contains(&jar_name) {
+                problem(category, format!("Missing {jar_name}"));
+            }
+        }
+    }
+
+    let artifacts = files.iter()
+        .filter(|name| name.starts_with(&base_name))
+        .filter(|name| crate::signing::is_signable(Path::new(name)));
+    for artifact in artifacts {
+        let signature = format!("{artifact}.{SIGNATURE_EXTENSION}");
+        if !files.contains(&signature) {
+            problem(RuleCategory::Signature, format!("Missing signature {signature}"));
+        }
+        for ext in CHECKSUM_EXTENSIONS {
+            let checksum = format!("{artifact}.{ext}");
+            if !files.contains(&checksum) {
+                problem(RuleCategory::Checksum, format!("Missing checksum {checksum}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_ref().is_none_or(|s| s.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bundle() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = temp.path();
+        let dir = root.join("org/example/demo/1.0");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("demo-1.0.pom"), r##"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <groupId>org.example</groupId>
+  <artifactId>demo</artifactId>
+  <version>1.0</version>
+  <name>Demo</name>
+  <url>https://example.org</url>
+  <licenses>
+    <license><name>MIT</name></license>
+  </licenses>
+  <dependencies>
+    <dependency><groupId>org.other</groupId><artifactId>lib</artifactId></dependency>
+  </dependencies>
+</project>
+"##)?;
+        for name in ["demo-1.0.pom.asc", "demo-1.0.pom.md5", "demo-1.0.pom.sha1", "demo-1.0.jar", "demo-1.0-sources.jar"] {
+            std::fs::write(dir.join(name), "")?;
+        }
+        let problems = validate_bundle(root);
+        let messages: Vec<(RuleCategory, String)> = problems?.into_iter()
+            .inspect(|p| assert_eq!(p.gav.to_string(), "org.example:demo:1.0"))
+            .map(|p| (p.category, p.message))
+            .collect();
+        assert_eq!(messages, vec![
+            (RuleCategory::Pom, "Missing <description> in demo-1.0.pom".to_string()),
+            (RuleCategory::Pom, "Missing <developers> in demo-1.0.pom".to_string()),
+            (RuleCategory::Pom, "Missing <scm> in demo-1.0.pom".to_string()),
+            (RuleCategory::Javadoc, "Missing demo-1.0-javadoc.jar".to_string()),
+            (RuleCategory::Signature, "Missing signature demo-1.0-sources.jar.asc".to_string()),
+            (RuleCategory::Checksum, "Missing checksum demo-1.0-sources.jar.md5".to_string()),
+            (RuleCategory::Checksum, "Missing checksum demo-1.0-sources.jar.sha1".to_string()),
+            (RuleCategory::Signature, "Missing signature demo-1.0.jar.asc".to_string()),
+            (RuleCategory::Checksum, "Missing checksum demo-1.0.jar.md5".to_string()),
+            (RuleCategory::Checksum, "Missing checksum demo-1.0.jar.sha1".to_string()),
+        ]);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Responses of various shapes, parsed by the model and serialized back.
+//!
+//! The fixtures under `tests/data` are synthetic - hand-written, not captured from live servers; see `tests/data/README.md`.
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use nexus_client::model::{ListOfObjects, ProfileMode, RepositoryState, StagingProfile, StagingProfileRepository};
+
+/// Fixtures which serialize back unchanged
+const SHAPES: [&str; 3] = ["without-optional", "complete", "extra-fields"];
+
+fn fixture(shape: &str, name: &str) -> anyhow::Result<Value> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data").join(shape).join(name);
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Parses the fixture, and checks that its serialization is identical to the original.
+fn parse_roundtrip<T: DeserializeOwned + Serialize>(shape: &str, name: &str) -> anyhow::Result<Vec<T>> {
+    let original = fixture(shape, name)?;
+    let parsed: ListOfObjects<T> = serde_json::from_value(original.clone())?;
+    assert_eq!(serde_json::to_value(&parsed)?, original, "roundtrip of {shape}/{name}");
+    Ok(parsed.data)
+}
+
+#[test]
+fn test_profile_repositories() -> anyhow::Result<()> {
+    for shape in SHAPES {
+        let repos: Vec<StagingProfileRepository> = parse_roundtrip(shape, "profile_repositories.json")?;
+        assert!(!repos.is_empty());
+        for repo in &repos {
+            assert!(repo.created_at().is_some());
+            assert!(repo.updated_at() >= repo.created_at());
+        }
+    }
+
+    let repos: Vec<StagingProfileRepository> = parse_roundtrip("without-optional", "profile_repositories.json")?;
+    assert_eq!(repos[0].state(), Some(RepositoryState::Released));
+    assert_eq!(repos[0].user_agent, None);
+    assert_eq!(repos[0].notifications, None);
+
+    let repos: Vec<StagingProfileRepository> = parse_roundtrip("complete", "profile_repositories.json")?;
+    assert_eq!(repos[1].state(), Some(RepositoryState::Closed));
+    assert_eq!(repos[1].description, "v1.2.3");
+    assert!(repos[1].extra.is_empty());
+
+    let repos: Vec<StagingProfileRepository> = parse_roundtrip("extra-fields", "profile_repositories.json")?;
+    assert_eq!(repos[0].transitioning, Some(true));
+    assert_eq!(repos[0].extra.get("autoDropAfterRelease"), Some(&Value::Bool(true)));
+    Ok(())
+}
+
+#[test]
+fn test_missing_strings() -> anyhow::Result<()> {
+    let original = fixture("missing-strings", "profile_repositories.json")?;
+    let repos: ListOfObjects<StagingProfileRepository> = serde_json::from_value(original.clone())?;
+    assert_eq!(repos.data[0].description, "");
+    assert_eq!(repos.data[0].state(), Some(RepositoryState::Open));
+
+    // missing text fields come back empty, not missing; everything else is unchanged
+    let mut expected = original;
+    let record = expected["data"][0].as_object_mut().unwrap();
+    for field in ["policy", "ipAddress", "description", "releaseRepositoryName"] {
+        assert_eq!(record.insert(field.to_string(), Value::String(String::new())), None, "{field} is in the fixture");
+    }
+    assert_eq!(serde_json::to_value(&repos)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_staging_profiles() -> anyhow::Result<()> {
+    for shape in SHAPES {
+        let profiles: Vec<StagingProfile> = parse_roundtrip(shape, "staging_profiles.json")?;
+        assert_eq!(profiles.len(), 1);
+    }
+
+    let profiles: Vec<StagingProfile> = parse_roundtrip("without-optional", "staging_profiles.json")?;
+    assert_eq!(profiles[0].profile_mode(), ProfileMode::Deploy);
+    assert_eq!(profiles[0].properties, None);
+
+    let profiles: Vec<StagingProfile> = parse_roundtrip("extra-fields", "staging_profiles.json")?;
+    assert_eq!(profiles[0].profile_mode(), ProfileMode::Upload);
+    let properties = profiles[0].properties.as_ref().unwrap();
+    assert_eq!(properties.class, "linked-hash-map");
+    assert_eq!(properties.extra.get("autoReleaseAfterClose"), Some(&Value::String("false".to_string())));
+    assert!(profiles[0].extra.contains_key("promotionTargetRepositoryName"));
+    Ok(())
+}
+
+#[test]
+fn test_minimal_repository() -> anyhow::Result<()> {
+    let repo: StagingProfileRepository = serde_json::from_str(r#"{"repositoryId": "x-1001", "type": "open"}"#)?;
+    assert_eq!(repo.repository_id, "x-1001");
+    assert_eq!(repo.state(), Some(RepositoryState::Open));
+    assert_eq!(repo.description, "");
+    Ok(())
+}
+
+#[test]
+fn test_missing_values_are_unknown() -> anyhow::Result<()> {
+    let repo: StagingProfileRepository = serde_json::from_str(r#"{"repositoryId": "x-1003"}"#)?;
+    assert_eq!(repo.state(), None);
+    assert_eq!(repo.transitioning, None);
+    assert_eq!(repo.created_at(), None);
+    assert_eq!(repo.updated_at(), None);
+    assert_eq!(serde_json::to_value(&repo)?.get("updatedTimestamp"), None);
+    Ok(())
+}
+
+#[test]
+fn test_raw_values_roundtrip() -> anyhow::Result<()> {
+    let original = serde_json::json!({"repositoryId": "x-1002", "type": "CLOSED", "policy": "Release"});
+    let repo: StagingProfileRepository = serde_json::from_value(original.clone())?;
+    assert_eq!(repo.state(), Some(RepositoryState::Closed));
+    assert_eq!(repo.repository_type, "CLOSED");
+    let serialized = serde_json::to_value(&repo)?;
+    assert_eq!(serialized["type"], original["type"]);
+    assert_eq!(serialized["policy"], original["policy"]);
+    Ok(())
+}